@@ -1,6 +1,16 @@
-CREATE TABLE chats (id INTEGER PRIMARY KEY AUTO_INCREMENT, telegram_id BIGINT NOT NULL, owner INTEGER, start_date DATETIME NOT NULL, end_date DATETIME DEFAULT NULL, players INTEGER NOT NULL DEFAULT 0, turn INTEGER NOT NULL DEFAULT 1, rando_carlissian BOOLEAN NOT NULL DEFAULT false, pick INTEGER NOT NULL DEFAULT 1);
-CREATE TABLE players (id INTEGER PRIMARY KEY AUTO_INCREMENT, telegram_id BIGINT NOT NULL, chat_id INTEGER NOT NULL, name VARCHAR(255) NOT NULL, turn INTEGER NOT NULL, points INTEGER NOT NULL DEFAULT 0, UNIQUE (id, chat_id));
+CREATE TABLE chats (id INTEGER PRIMARY KEY AUTO_INCREMENT, telegram_id BIGINT NOT NULL, owner INTEGER, start_date DATETIME NOT NULL, end_date DATETIME DEFAULT NULL, players INTEGER NOT NULL DEFAULT 0, turn INTEGER NOT NULL DEFAULT 1, rando_carlissian BOOLEAN NOT NULL DEFAULT false, pick INTEGER NOT NULL DEFAULT 1, started BOOLEAN NOT NULL DEFAULT false, global_leaderboard BOOLEAN NOT NULL DEFAULT true, peoples_choice BOOLEAN NOT NULL DEFAULT false, god_is_dead BOOLEAN NOT NULL DEFAULT false, rotation VARCHAR(16) NOT NULL DEFAULT 'round_robin', judge_turn INTEGER NOT NULL DEFAULT 1, reboot BOOLEAN NOT NULL DEFAULT false, discards INTEGER NOT NULL DEFAULT 0, gambling BOOLEAN NOT NULL DEFAULT false, survival BOOLEAN NOT NULL DEFAULT false, single_pick BOOLEAN NOT NULL DEFAULT false, reveal_submissions BOOLEAN NOT NULL DEFAULT false, redrawn_turn INTEGER NOT NULL DEFAULT 0);
+CREATE TABLE players (id INTEGER PRIMARY KEY AUTO_INCREMENT, telegram_id BIGINT NOT NULL, chat_id INTEGER NOT NULL, name VARCHAR(255) NOT NULL, turn INTEGER NOT NULL, points INTEGER NOT NULL DEFAULT 0, discards INTEGER NOT NULL DEFAULT 0, UNIQUE (id, chat_id));
 CREATE TABLE packs (id INTEGER PRIMARY KEY AUTO_INCREMENT, name VARCHAR(255) NOT NULL, official BOOLEAN NOT NULL DEFAULT false);
 CREATE TABLE cards (id INTEGER PRIMARY KEY AUTO_INCREMENT, pack_id INTEGER NOT NULL, color CHAR(5) NOT NULL, pick INTEGER DEFAULT NULL, text VARCHAR(255) NOT NULL);
-CREATE TABLE hands (id INTEGER PRIMARY KEY AUTO_INCREMENT, player_id INTEGER NOT NULL, chat_id INTEGER NOT NULL, card_id INTEGER NOT NULL, picked_on_turn INTEGER NOT NULL, played_on_turn INTEGER DEFAULT NULL, seq INTEGER NOT NULL DEFAULT 0, won BOOLEAN NOT NULL DEFAULT false);
+CREATE TABLE hands (id INTEGER PRIMARY KEY AUTO_INCREMENT, player_id INTEGER NOT NULL, chat_id INTEGER NOT NULL, card_id INTEGER NOT NULL, picked_on_turn INTEGER NOT NULL, played_on_turn INTEGER DEFAULT NULL, seq INTEGER NOT NULL DEFAULT 0, won BOOLEAN NOT NULL DEFAULT false, discarded BOOLEAN NOT NULL DEFAULT false, wager BOOLEAN NOT NULL DEFAULT false, eliminated BOOLEAN NOT NULL DEFAULT false);
 CREATE TABLE chat_packs (chat_id INTEGER, pack_id INTEGER, PRIMARY KEY (chat_id, pack_id));
+CREATE TABLE chat_defaults (telegram_id BIGINT PRIMARY KEY, rando_carlissian BOOLEAN NOT NULL DEFAULT false, global_leaderboard BOOLEAN NOT NULL DEFAULT true, peoples_choice BOOLEAN NOT NULL DEFAULT false, god_is_dead BOOLEAN NOT NULL DEFAULT false, rotation VARCHAR(16) NOT NULL DEFAULT 'round_robin', reboot BOOLEAN NOT NULL DEFAULT false, discards INTEGER NOT NULL DEFAULT 0, gambling BOOLEAN NOT NULL DEFAULT false, survival BOOLEAN NOT NULL DEFAULT false, single_pick BOOLEAN NOT NULL DEFAULT false, reveal_submissions BOOLEAN NOT NULL DEFAULT false);
+CREATE TABLE chat_default_packs (telegram_id BIGINT, pack_id INTEGER, PRIMARY KEY (telegram_id, pack_id));
+CREATE TABLE hall_of_fame (id INTEGER PRIMARY KEY AUTO_INCREMENT, telegram_id BIGINT NOT NULL, chat_id INTEGER NOT NULL, turn INTEGER NOT NULL, player_id INTEGER NOT NULL, text VARCHAR(2048) NOT NULL, votes INTEGER NOT NULL DEFAULT 0);
+CREATE TABLE hall_of_fame_votes (hall_of_fame_id INTEGER, telegram_id BIGINT, PRIMARY KEY (hall_of_fame_id, telegram_id));
+CREATE TABLE ratings (telegram_id BIGINT PRIMARY KEY, rating INTEGER NOT NULL DEFAULT 1500, games INTEGER NOT NULL DEFAULT 0);
+CREATE TABLE achievements (telegram_id BIGINT, kind VARCHAR(16), chat_id INTEGER NOT NULL, unlocked_on DATETIME NOT NULL, PRIMARY KEY (telegram_id, kind));
+CREATE TABLE scores (telegram_id BIGINT, week DATE, rounds_won INTEGER NOT NULL DEFAULT 0, games_won INTEGER NOT NULL DEFAULT 0, PRIMARY KEY (telegram_id, week));
+CREATE TABLE seasons (id INTEGER PRIMARY KEY AUTO_INCREMENT, start_date DATE NOT NULL, end_date DATE NOT NULL);
+CREATE TABLE season_standings (season_id INTEGER, chat_id BIGINT, telegram_id BIGINT, position INTEGER NOT NULL, games_won INTEGER NOT NULL, points INTEGER NOT NULL, PRIMARY KEY (season_id, chat_id, telegram_id));
+CREATE TABLE votes (chat_id INTEGER, turn INTEGER, voter_id INTEGER, player_id INTEGER NOT NULL, PRIMARY KEY (chat_id, turn, voter_id));
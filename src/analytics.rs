@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use futures_util::TryStreamExt;
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, JoinType, QueryFilter,
+    QuerySelect, StreamTrait,
+};
+
+use crate::entities::{card, chat, hand, pack};
+
+/// minimum number of plays for a card to be ranked by win rate
+const MIN_PLAYS: usize = 3;
+const TOP: usize = 10;
+
+#[derive(Default)]
+struct Counter {
+    played: usize,
+    won: usize,
+}
+
+impl Counter {
+    fn win_rate(&self) -> usize {
+        (self.won * 100).checked_div(self.played).unwrap_or_default()
+    }
+}
+
+/// card performances, computed from played hands
+pub struct CardStats {
+    turns: usize,
+    cards: HashMap<i32, card::Model>,
+    packs: HashMap<i32, pack::Model>,
+    white: HashMap<i32, Counter>,
+    pairings: HashMap<(i32, i32), Counter>,
+    by_pack: HashMap<i32, Counter>,
+}
+
+/// computes card stats of given games, or of every game if `None`
+pub async fn card_stats<C>(conn: &C, chat_ids: Option<&[i32]>) -> Result<CardStats, DbErr>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    // only completed turns count, in closed games too the last turn is left unfinished,
+    // Rando Carlissian plays at random
+    let mut filter = hand::Column::PlayedOnTurn
+        .is_not_null()
+        .and(hand::Column::PlayerId.ne(0))
+        .and(
+            Expr::col((hand::Entity, hand::Column::PlayedOnTurn))
+                .lt(Expr::col((chat::Entity, chat::Column::Turn))),
+        );
+    if let Some(chat_ids) = chat_ids {
+        filter = filter.and(hand::Column::ChatId.is_in(chat_ids.iter().copied()));
+    }
+    let hands = hand::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            hand::Entity::belongs_to(chat::Entity)
+                .from(hand::Column::ChatId)
+                .to(chat::Column::Id)
+                .into(),
+        )
+        .filter(filter)
+        .stream(conn)
+        .await?
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    let mut card_ids = hands.iter().map(|hand| hand.card_id).collect::<Vec<_>>();
+    card_ids.sort_unstable();
+    card_ids.dedup();
+    let mut cards = HashMap::with_capacity(card_ids.len());
+    // avoid hitting query parameters limit
+    for chunk in card_ids.chunks(1000) {
+        let stream = card::Entity::find()
+            .filter(card::Column::Id.is_in(chunk.iter().copied()))
+            .stream(conn)
+            .await?;
+        cards.extend(
+            stream
+                .map_ok(|card| (card.id, card))
+                .try_collect::<Vec<_>>()
+                .await?,
+        );
+    }
+    let packs = pack::Entity::find()
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|pack| (pack.id, pack))
+        .collect();
+
+    let mut stats = CardStats {
+        turns: 0,
+        cards,
+        packs,
+        white: HashMap::new(),
+        pairings: HashMap::new(),
+        by_pack: HashMap::new(),
+    };
+
+    // black card and played white cards, with their outcome, of every turn
+    let mut turns = HashMap::<_, (Option<i32>, Vec<(i32, bool)>)>::new();
+    for hand in &hands {
+        let Some(card) = stats.cards.get(&hand.card_id) else {
+            continue;
+        };
+        let turn = turns
+            .entry((hand.chat_id, hand.played_on_turn))
+            .or_default();
+        if card.color == card::Color::Black {
+            turn.0 = Some(card.id);
+            continue;
+        }
+
+        let white = stats.white.entry(card.id).or_default();
+        white.played += 1;
+        let by_pack = stats.by_pack.entry(card.pack_id).or_default();
+        by_pack.played += 1;
+        if hand.won {
+            white.won += 1;
+            by_pack.won += 1;
+        }
+        turn.1.push((card.id, hand.won));
+    }
+
+    for (black_id, whites) in turns.into_values() {
+        let Some(black_id) = black_id else {
+            continue;
+        };
+        stats.turns += 1;
+        for (white_id, won) in whites {
+            let pairing = stats.pairings.entry((black_id, white_id)).or_default();
+            pairing.played += 1;
+            if won {
+                pairing.won += 1;
+            }
+        }
+    }
+
+    Ok(stats)
+}
+
+impl CardStats {
+    /// human readable report, every text goes through `fmt` for escaping
+    pub fn report(&self, fmt: impl Fn(&str) -> String) -> String {
+        let text = |card_id: &i32| {
+            self.cards
+                .get(card_id)
+                .map(|card| fmt(card.plain_text()))
+                .unwrap_or_default()
+        };
+
+        let mut out = format!("Card stats over {} turns\n", self.turns);
+
+        let mut white = self
+            .white
+            .iter()
+            .filter(|(_, counter)| counter.played >= MIN_PLAYS)
+            .collect::<Vec<_>>();
+        white.sort_by(|(id_a, a), (id_b, b)| {
+            b.win_rate()
+                .cmp(&a.win_rate())
+                .then(b.played.cmp(&a.played))
+                .then(id_a.cmp(id_b))
+        });
+        out.push_str(&format!(
+            "\nBest white cards, played at least {MIN_PLAYS} times:"
+        ));
+        for (card_id, counter) in white.into_iter().take(TOP) {
+            out.push_str(&format!(
+                "\n{}% {} {}/{}",
+                counter.win_rate(),
+                text(card_id),
+                counter.won,
+                counter.played
+            ));
+        }
+
+        let mut pairings = self
+            .pairings
+            .iter()
+            .filter(|(_, counter)| counter.won > 0 && counter.played >= MIN_PLAYS)
+            .collect::<Vec<_>>();
+        pairings.sort_by(|(ids_a, a), (ids_b, b)| {
+            b.win_rate()
+                .cmp(&a.win_rate())
+                .then(b.played.cmp(&a.played))
+                .then(ids_a.cmp(ids_b))
+        });
+        out.push_str(&format!(
+            "\n\nBest pairings, played together at least {MIN_PLAYS} times:"
+        ));
+        for ((black_id, white_id), counter) in pairings.into_iter().take(TOP) {
+            out.push_str(&format!(
+                "\n{}% {} {} {} {}/{}",
+                counter.win_rate(),
+                text(black_id),
+                fmt("+"),
+                text(white_id),
+                counter.won,
+                counter.played
+            ));
+        }
+
+        let mut by_pack = self.by_pack.iter().collect::<Vec<_>>();
+        by_pack.sort_by(|(id_a, a), (id_b, b)| {
+            b.win_rate()
+                .cmp(&a.win_rate())
+                .then(b.played.cmp(&a.played))
+                .then(id_a.cmp(id_b))
+        });
+        out.push_str("\n\nPacks by white cards win rate:");
+        for (pack_id, counter) in by_pack {
+            out.push_str(&format!(
+                "\n{}% {} {}/{}",
+                counter.win_rate(),
+                self.packs
+                    .get(pack_id)
+                    .map(|pack| fmt(pack.plain_name()))
+                    .unwrap_or_default(),
+                counter.won,
+                counter.played
+            ));
+        }
+
+        out
+    }
+}
@@ -1,5 +1,6 @@
 use std::time::Duration;
 
+use chrono::Utc;
 use sea_orm::{ConnectionTrait, StreamTrait, TransactionTrait};
 use tgbot::{
     api::{Client, ExecuteError},
@@ -11,7 +12,7 @@ use tgbot::{
 use tokio::time;
 use tracing::{debug, error, warn};
 
-use crate::Error;
+use crate::{entities::season, Error};
 
 mod parser;
 
@@ -26,14 +27,29 @@ fn clear_error(res: Result<(), Error>) -> Result<(), Error> {
     res
 }
 
-pub async fn execute<C>(conn: &C, token: String, name: &str) -> Result<(), Error>
+pub async fn execute<C>(
+    conn: &C,
+    token: String,
+    name: &str,
+    period: season::Period,
+) -> Result<(), Error>
 where
     C: ConnectionTrait + StreamTrait + TransactionTrait,
 {
     let client = Client::new(token)?;
 
     let mut offset = -1;
+    // end of the current season, seasons are checked again only once it's passed
+    let mut season_end = None;
     loop {
+        // long polling returns at least once an hour
+        if season_end.is_none_or(|end| Utc::now().date_naive() >= end) {
+            match parser::season_rollover(&client, conn, period).await {
+                Ok(()) => season_end = Some(period.current().1),
+                Err(err) => error!("Season rollover error: {err}"),
+            }
+        }
+
         let updates = match client
             .execute(
                 GetUpdates::default()
@@ -61,10 +77,15 @@ where
                     ref chat,
                     data: MessageData::Text(Text { ref data, .. }),
                     ..
-                }) => parser::parse_message(&client, conn, name, user, id, data, chat).await,
+                }) => {
+                    parser::parse_message(&client, conn, name, period, user, id, data, chat).await
+                }
                 UpdateType::InlineQuery(InlineQuery {
-                    ref id, ref query, ..
-                }) => parser::parse_inline_query(&client, conn, user, id, query).await,
+                    ref id,
+                    ref query,
+                    ref offset,
+                    ..
+                }) => parser::parse_inline_query(&client, conn, user, id, query, offset).await,
                 UpdateType::ChosenInlineResult(ChosenInlineResult { ref result_id, .. }) => {
                     parser::parse_inline_query_response(&client, conn, user, result_id).await
                 }
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, StreamTrait};
+use tgbot::{
+    api::Client,
+    types::{ParseMode, ReplyParameters, SendMessage},
+};
+
+use crate::{
+    entities::{chat, player},
+    Error,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum AlltimeError {
+    #[error("There are no closed games in this chat")]
+    NoGames,
+}
+
+#[derive(Default)]
+struct Stats {
+    games: i64,
+    won: i64,
+    points: i64,
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    message_id: i64,
+    chat: &chat::Model,
+) -> Result<Result<(), AlltimeError>, Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let chat_ids = chat::Entity::find()
+        .filter(
+            chat::Column::TelegramId
+                .eq(chat.telegram_id)
+                .and(chat::Column::EndDate.is_not_null()),
+        )
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|chat| chat.id)
+        .collect::<Vec<_>>();
+    if chat_ids.is_empty() {
+        return Ok(Err(AlltimeError::NoGames));
+    }
+
+    // latest name wins
+    let names = player::Entity::find()
+        .filter(player::Column::ChatId.is_in(chat_ids.iter().copied()))
+        .order_by_asc(player::Column::Id)
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|player| (player.telegram_id, player.tg_link()))
+        .collect::<HashMap<_, _>>();
+
+    let mut stats = HashMap::<_, Stats>::new();
+    for standings in chat::standings(conn, &chat_ids).await?.values() {
+        for (telegram_id, points) in standings {
+            let stats = stats.entry(*telegram_id).or_default();
+            stats.games += 1;
+            stats.points += points;
+        }
+        for telegram_id in chat::winners(standings) {
+            stats.entry(telegram_id).or_default().won += 1;
+        }
+    }
+
+    let mut stats = stats.into_iter().collect::<Vec<_>>();
+    stats.sort_by(|(_, a), (_, b)| b.won.cmp(&a.won).then(b.points.cmp(&a.points)));
+
+    let mut msg = format!("All time ranking after {} games\n", chat_ids.len());
+    for (telegram_id, stats) in stats {
+        let Some(name) = names
+            .get(&telegram_id)
+            .map(String::as_str)
+            .or((telegram_id == 0).then_some(crate::RANDO_CARLISSIAN))
+        else {
+            continue;
+        };
+        msg.push_str(&format!(
+            "\n{name} {} game{} won out of {}, {} points",
+            stats.won,
+            if stats.won == 1 { "" } else { "s" },
+            stats.games,
+            stats.points,
+        ));
+    }
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(Ok(()))
+}
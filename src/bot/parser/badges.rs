@@ -0,0 +1,55 @@
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, Iterable, QueryFilter};
+use tgbot::{
+    api::Client,
+    types::{ParseMode, ReplyParameters, SendMessage, User},
+};
+
+use crate::{
+    entities::{achievement, chat},
+    utils::escape_markdown,
+    Error,
+};
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    message_id: i64,
+    chat: &chat::Model,
+) -> Result<(), Error>
+where
+    C: ConnectionTrait,
+{
+    let unlocked = achievement::Entity::find()
+        .filter(achievement::Column::TelegramId.eq(i64::from(user.id)))
+        .all(conn)
+        .await?;
+
+    let mut msg = format!(
+        "{} badges, {}/{} unlocked\n",
+        escape_markdown(&user.first_name),
+        unlocked.len(),
+        achievement::Kind::iter().count()
+    );
+    for kind in achievement::Kind::iter() {
+        match unlocked.iter().find(|achievement| achievement.kind == kind) {
+            Some(achievement) => msg.push_str(&format!(
+                "\n🏅 *{}*: {}, unlocked on {}",
+                kind.title(),
+                kind.descr(),
+                escape_markdown(achievement.unlocked_on.format("%Y-%m-%d").to_string()),
+            )),
+            None => msg.push_str(&format!("\n🔒 {}: {}", kind.title(), kind.descr())),
+        }
+    }
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QuerySelect, TransactionTrait,
+};
+use tgbot::{
+    api::Client,
+    types::{InlineKeyboardButton, ParseMode, ReplyParameters, SendMessage, User},
+};
+
+use crate::{
+    entities::{card, chat, chat_pack, player},
+    Error,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum BeginError {
+    #[error("You're not the game owner, only {0} can use this command")]
+    NotOwner(String),
+    #[error("The game has already begun")]
+    AlreadyStarted,
+    #[error(
+        "Not enough players in the game, you need at least 3 players, {} included",
+        crate::RANDO_CARLISSIAN
+    )]
+    NotEnoughPlayers,
+    #[error("Not enough black cards in enabled packs, enable more packs from /settings")]
+    NotEnoughBlackCards,
+    #[error("Not enough white cards in enabled packs, enable more packs from /settings")]
+    NotEnoughWhiteCards,
+    #[error(transparent)]
+    Chat(#[from] chat::ChatError),
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    message_id: i64,
+    chat: &chat::Model,
+) -> Result<Result<(), BeginError>, Error>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let Some(player) = player::Entity::find()
+        .filter(
+            player::Column::TelegramId
+                .eq(i64::from(user.id))
+                .and(player::Column::ChatId.eq(chat.id)),
+        )
+        .one(conn)
+        .await?
+    else {
+        return Ok(Ok(()));
+    };
+
+    if chat.owner != Some(player.id) {
+        let Some(owner) = player::Entity::find_by_id(chat.owner.unwrap_or_default())
+            .one(conn)
+            .await?
+        else {
+            return Ok(Ok(()));
+        };
+
+        return Ok(Err(BeginError::NotOwner(owner.tg_link())));
+    }
+
+    if chat.started {
+        return Ok(Err(BeginError::AlreadyStarted));
+    }
+
+    if let Err(e) = validate(conn, chat).await? {
+        return Ok(Err(e));
+    }
+
+    let txn = conn.begin().await?;
+
+    let chat = chat::ActiveModel {
+        id: ActiveValue::Set(chat.id),
+        started: ActiveValue::Set(true),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await?;
+
+    let msg = match chat.reset(&txn).await? {
+        Ok(msg) => msg,
+        Err(e) => return Ok(Err(BeginError::from(e))),
+    };
+    txn.commit().await?;
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_markup(
+                    [[InlineKeyboardButton::for_switch_inline_query_current_chat(
+                        "Open cards hand",
+                        chat.id.to_string(),
+                    )]],
+                )
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(Ok(()))
+}
+
+/// checks there are enough players and cards to play the game
+pub async fn validate<C>(conn: &C, chat: &chat::Model) -> Result<Result<(), BeginError>, Error>
+where
+    C: ConnectionTrait,
+{
+    // rando carlissian counts as a player
+    let players = chat.players + chat.rando_carlissian as i32;
+    if 3 > players {
+        return Ok(Err(BeginError::NotEnoughPlayers));
+    }
+
+    let enabled_packs = chat_pack::Entity::find()
+        .filter(chat_pack::Column::ChatId.eq(chat.id))
+        .select_only()
+        .column(chat_pack::Column::PackId)
+        .into_tuple::<i32>()
+        .all(conn)
+        .await?;
+    if count_cards(conn, &enabled_packs, card::Color::Black, chat.single_pick).await? == 0 {
+        return Ok(Err(BeginError::NotEnoughBlackCards));
+    }
+    // every player needs a full hand, plus a few cards to play the first turn
+    if count_cards(conn, &enabled_packs, card::Color::White, false).await? < players as i64 * 10 + 3
+    {
+        return Ok(Err(BeginError::NotEnoughWhiteCards));
+    }
+
+    Ok(Ok(()))
+}
+
+/// counts cards of given color in enabled packs, black cards only pick 1 if `single_pick`
+async fn count_cards<C>(
+    conn: &C,
+    enabled_packs: &[i32],
+    color: card::Color,
+    single_pick: bool,
+) -> Result<i64, Error>
+where
+    C: ConnectionTrait,
+{
+    let mut filter = card::Column::Color
+        .eq(color)
+        .and(card::Column::PackId.is_in(enabled_packs.iter().copied()));
+    if single_pick {
+        filter = filter.and(card::Column::Pick.is_null().or(card::Column::Pick.lte(1)));
+    }
+
+    Ok(card::Entity::find()
+        .filter(filter)
+        .select_only()
+        .column_as(card::Column::Id.count(), "count")
+        .into_tuple::<Option<i64>>()
+        .one(conn)
+        .await?
+        .flatten()
+        .unwrap_or_default())
+}
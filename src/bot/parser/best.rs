@@ -0,0 +1,154 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use tgbot::{
+    api::Client,
+    types::{
+        EditMessageReplyMarkup, InlineKeyboardButton, ParseMode, ReplyParameters, SendMessage, User,
+    },
+};
+
+use crate::{
+    entities::{chat, hall_of_fame, player},
+    Error,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum BestError {
+    #[error("The hall of fame of this chat is empty")]
+    Empty,
+}
+
+/// upvote buttons for the winning combinations of a turn,
+/// labelled with the winner's name when there's more than one
+pub async fn keyboard<C>(
+    conn: &C,
+    best: &[hall_of_fame::Model],
+) -> Result<Vec<Vec<InlineKeyboardButton>>, Error>
+where
+    C: ConnectionTrait,
+{
+    let names = if best.len() > 1 {
+        player::Entity::find()
+            .filter(player::Column::Id.is_in(best.iter().map(|best| best.player_id)))
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|player| (player.id, player.plain_name().to_owned()))
+            .chain([(0, crate::RANDO_CARLISSIAN.to_owned())])
+            .collect::<HashMap<_, _>>()
+    } else {
+        HashMap::new()
+    };
+
+    Ok(best
+        .iter()
+        .map(|best| {
+            let label = match names.get(&best.player_id) {
+                Some(name) => format!("👍 {} {name}", best.votes),
+                None => format!("👍 {}", best.votes),
+            };
+            vec![InlineKeyboardButton::for_callback_data(
+                label,
+                format!("best {}", best.id),
+            )]
+        })
+        .collect())
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    message_id: i64,
+    chat: &chat::Model,
+) -> Result<Result<(), BestError>, Error>
+where
+    C: ConnectionTrait,
+{
+    let best = hall_of_fame::Entity::find()
+        .filter(
+            hall_of_fame::Column::TelegramId
+                .eq(chat.telegram_id)
+                .and(hall_of_fame::Column::Votes.gt(0)),
+        )
+        .order_by_desc(hall_of_fame::Column::Votes)
+        .order_by_asc(hall_of_fame::Column::Id)
+        .limit(10)
+        .all(conn)
+        .await?;
+    if best.is_empty() {
+        return Ok(Err(BestError::Empty));
+    }
+
+    let names = player::Entity::find()
+        .filter(player::Column::Id.is_in(best.iter().map(|best| best.player_id)))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|player| (player.id, Cow::Owned(player.tg_link())))
+        .chain([(0, Cow::Borrowed(crate::RANDO_CARLISSIAN))])
+        .collect::<HashMap<_, _>>();
+
+    let mut msg = String::from("Hall of fame");
+    for (position, best) in best.into_iter().enumerate() {
+        msg.push_str(&format!(
+            "\n\n{}\\. {}\nby {}, 👍 {}",
+            position + 1,
+            best.text,
+            names
+                .get(&best.player_id)
+                .map(Cow::as_ref)
+                .unwrap_or_default(),
+            best.votes,
+        ));
+    }
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(Ok(()))
+}
+
+pub async fn vote<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    message_id: i64,
+    chat: &chat::Model,
+    query_data: &str,
+) -> Result<(), Error>
+where
+    C: ConnectionTrait,
+{
+    let Some(Ok(id)) = query_data.strip_prefix("best ").map(str::parse::<i32>) else {
+        return Ok(());
+    };
+
+    let Some(best) = hall_of_fame::vote(conn, id, chat, i64::from(user.id)).await? else {
+        return Ok(());
+    };
+    // ties share the same recap message
+    let turn = hall_of_fame::Entity::find()
+        .filter(
+            hall_of_fame::Column::ChatId
+                .eq(best.chat_id)
+                .and(hall_of_fame::Column::Turn.eq(best.turn)),
+        )
+        .order_by_asc(hall_of_fame::Column::Id)
+        .all(conn)
+        .await?;
+
+    client
+        .execute(
+            EditMessageReplyMarkup::for_chat_message(chat.telegram_id, message_id)
+                .with_reply_markup(keyboard(conn, &turn).await?),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect, StreamTrait};
+use tgbot::{
+    api::Client,
+    types::{ParseMode, ReplyParameters, SendMessage},
+};
+
+use crate::{entities::chat, utils::escape_markdown, Error};
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    message_id: i64,
+    chat: &chat::Model,
+) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let chat_ids = chat::Entity::find()
+        .filter(chat::Column::TelegramId.eq(chat.telegram_id))
+        .select_only()
+        .column(chat::Column::Id)
+        .into_tuple::<i32>()
+        .all(conn)
+        .await?;
+
+    let stats = crate::analytics::card_stats(conn, Some(&chat_ids)).await?;
+    let mut report = stats.report(|text| escape_markdown(text));
+    // Telegram messages are limited to 4096 characters, cut at the last whole line
+    if let Some((index, _)) = report.char_indices().nth(4090) {
+        report.truncate(report[..index].rfind('\n').unwrap_or(index));
+        report.push_str("\n…");
+    }
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, report)
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(())
+}
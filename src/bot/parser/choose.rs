@@ -1,6 +1,12 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+};
+
+use futures_util::TryStreamExt;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
-    QuerySelect, StreamTrait, TransactionTrait,
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, StreamTrait, TransactionTrait,
 };
 use tgbot::{
     api::Client,
@@ -8,7 +14,7 @@ use tgbot::{
 };
 
 use crate::{
-    entities::{chat, hand, player},
+    entities::{achievement, card, chat, hall_of_fame, hand, player, score, vote},
     Error,
 };
 
@@ -45,24 +51,103 @@ where
 
     // when you're the judge
     let res = if player.is_my_turn(&chat) {
-        as_judge(client, conn, &chat, hands).await?
+        as_judge(client, conn, &player, &chat, hands).await?
     } else {
-        if hands.len() != 1 {
-            return Ok(());
-        }
-
-        as_player(client, conn, &player, &chat, &hands[0]).await?
+        as_player(client, conn, &player, &chat, hands).await?
     };
 
     if let Err(e) = res {
-        let msg = match chat.close(conn).await? {
-            Ok(msg) => format!("{e}\n\n{msg}"),
-            Err(err) => format!("{e}\n\nError: {err}"),
+        game_over(client, conn, &chat, e).await?;
+    }
+
+    Ok(())
+}
+
+/// closes the game when the next turn can't start
+pub async fn game_over<C>(
+    client: &Client,
+    conn: &C,
+    chat: &chat::Model,
+    e: chat::ChatError,
+) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let msg = match chat.close(conn).await? {
+        Ok(msg) => format!("{e}\n\n{msg}"),
+        Err(err) => format!("{e}\n\nError: {err}"),
+    };
+
+    client
+        .execute(SendMessage::new(chat.telegram_id, msg).with_parse_mode(ParseMode::MarkdownV2))
+        .await?;
+
+    Ok(())
+}
+
+/// judge's verdict other than a single winner: a tie between submissions,
+/// given their player id and wager, or a void turn when empty
+///
+/// a tie is between different players, a submission can't tie with the same player's wager;
+/// without a single winner nobody collects the wagered points, so every gambler, winning or not,
+/// gets the point back
+pub async fn verdict<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    chat_id: i32,
+    winners: &[(i32, bool)],
+) -> Result<(), Error>
+where
+    C: ConnectionTrait + TransactionTrait + StreamTrait,
+{
+    let Some(chat) = chat::Entity::find_by_id(chat_id).one(conn).await? else {
+        return Ok(());
+    };
+    // stale inline results, verdicts are given only once everyone played
+    if chat.end_date.is_some() || chat.survival || !chat.all_played(conn).await? {
+        return Ok(());
+    }
+
+    let Some(judge) = player::Entity::find()
+        .filter(
+            player::Column::TelegramId
+                .eq(i64::from(user.id))
+                .and(player::Column::ChatId.eq(chat.id)),
+        )
+        .one(conn)
+        .await?
+    else {
+        return Ok(());
+    };
+    if !judge.is_my_turn(&chat) {
+        return Ok(());
+    }
+
+    if !winners.is_empty() {
+        let mut player_ids = winners
+            .iter()
+            .map(|(player_id, _)| *player_id)
+            .collect::<Vec<_>>();
+        player_ids.sort_unstable();
+        player_ids.dedup();
+        if player_ids.len() != winners.len() {
+            return Ok(());
+        }
+
+        let Some(round) = chat.current_round(conn).await? else {
+            return Ok(());
         };
+        if winners
+            .iter()
+            .any(|winner| !round.submissions.contains_key(winner))
+        {
+            return Ok(());
+        }
+    }
 
-        client
-            .execute(SendMessage::new(chat.telegram_id, msg).with_parse_mode(ParseMode::MarkdownV2))
-            .await?;
+    if let Err(e) = end_turn(client, conn, Some(&judge), &chat, winners).await? {
+        game_over(client, conn, &chat, e).await?;
     }
 
     Ok(())
@@ -71,51 +156,232 @@ where
 async fn as_judge<C>(
     client: &Client,
     conn: &C,
+    judge: &player::Model,
     chat: &chat::Model,
     hands: &[hand::Model],
 ) -> Result<Result<(), chat::ChatError>, Error>
+where
+    C: ConnectionTrait + TransactionTrait + StreamTrait,
+{
+    let mut submissions = hands
+        .iter()
+        .map(|hand: &hand::Model| (hand.player_id, hand.wager))
+        .collect::<Vec<_>>();
+    submissions.dedup();
+    if submissions.len() != 1 {
+        return Ok(Ok(()));
+    }
+
+    if chat.survival {
+        return eliminate(client, conn, judge, chat, submissions[0]).await;
+    }
+
+    end_turn(client, conn, Some(judge), chat, &submissions).await
+}
+
+/// survival of the fittest, the judge cuts a submission at a time until only one is left
+async fn eliminate<C>(
+    client: &Client,
+    conn: &C,
+    judge: &player::Model,
+    chat: &chat::Model,
+    (player_id, wager): (i32, bool),
+) -> Result<Result<(), chat::ChatError>, Error>
+where
+    C: ConnectionTrait + TransactionTrait + StreamTrait,
+{
+    // stale inline results, eliminations start once everyone played
+    if chat.end_date.is_some() || !judge.is_my_turn(chat) || !chat.all_played(conn).await? {
+        return Ok(Ok(()));
+    }
+
+    let Some(round) = chat.current_round(conn).await? else {
+        return Ok(Ok(()));
+    };
+    let Some(black_card) = &round.black_card else {
+        return Ok(Ok(()));
+    };
+    match round.submissions.get(&(player_id, wager)) {
+        Some(submission) if !submission.iter().any(|(hand, _)| hand.eliminated) => {}
+        // already eliminated
+        _ => return Ok(Ok(())),
+    }
+
+    hand::Entity::update_many()
+        .col_expr(hand::Column::Eliminated, Expr::value(true))
+        .filter(
+            hand::Column::ChatId
+                .eq(chat.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn))
+                .and(hand::Column::PlayerId.eq(player_id))
+                .and(hand::Column::Wager.eq(wager)),
+        )
+        .exec(conn)
+        .await?;
+
+    let standing = round
+        .submissions
+        .iter()
+        .filter(|(key, submission)| {
+            **key != (player_id, wager)
+                && submission.len() >= chat.pick as usize
+                && !submission.iter().any(|(hand, _)| hand.eliminated)
+        })
+        .collect::<Vec<_>>();
+
+    if let [(survivor, _)] = standing[..] {
+        return end_turn(client, conn, Some(judge), chat, &[*survivor]).await;
+    }
+
+    let mut msg = format!(
+        "{} submissions still standing, {} can eliminate another one:",
+        standing.len(),
+        judge.tg_link()
+    );
+    for (_, submission) in standing {
+        msg.push_str(&format!(
+            "\n\n{}",
+            black_card.fill(&submission.iter().map(|(_, card)| card).collect::<Vec<_>>())
+        ));
+    }
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_markup(
+                    [[InlineKeyboardButton::for_switch_inline_query_current_chat(
+                        "Open cards hand",
+                        chat.id.to_string(),
+                    )]],
+                )
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(Ok(()))
+}
+
+/// awards the winning submissions, by player id and wager, and starts the next turn
+pub async fn end_turn<C>(
+    client: &Client,
+    conn: &C,
+    judge: Option<&player::Model>,
+    chat: &chat::Model,
+    winners: &[(i32, bool)],
+) -> Result<Result<(), chat::ChatError>, Error>
 where
     C: ConnectionTrait + TransactionTrait,
 {
     let txn = conn.begin().await?;
 
-    let mut player_ids = hands
+    let mut winner_ids = winners
         .iter()
-        .map(|hand: &hand::Model| hand.player_id)
+        .map(|(player_id, _)| *player_id)
         .collect::<Vec<_>>();
-    player_ids.dedup();
-    if player_ids.len() != 1 {
-        return Ok(Ok(()));
+    winner_ids.dedup();
+
+    // points earned by player id
+    let mut points = winner_ids
+        .iter()
+        .map(|player_id| (*player_id, 1))
+        .collect::<HashMap<_, _>>();
+
+    // bonus point for the submissions most voted by the other players,
+    // when god is dead votes already choose the winners
+    let favourites = if chat.peoples_choice && !chat.god_is_dead {
+        vote::favourites(&txn, chat).await?
+    } else {
+        Vec::new()
+    };
+    for favourite in &favourites {
+        *points.entry(*favourite).or_default() += 1;
     }
-    let player_id = player_ids[0];
 
-    if player_id > 0 {
+    // gamblers get their wager back when winning, otherwise it goes to the single winner,
+    // when there's no single winner to collect it (void turn, tie or Rando Carlissian)
+    // the wager is refunded
+    let gamblers = hand::Entity::find()
+        .filter(
+            hand::Column::ChatId
+                .eq(chat.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn))
+                .and(hand::Column::Wager.eq(true)),
+        )
+        .select_only()
+        .column(hand::Column::PlayerId)
+        .distinct()
+        .into_tuple::<i32>()
+        .all(&txn)
+        .await?;
+    for gambler in gamblers {
+        match winner_ids[..] {
+            [winner_id] if winner_id > 0 && winner_id != gambler => {
+                *points.entry(winner_id).or_default() += 1
+            }
+            _ => *points.entry(gambler).or_default() += 1,
+        }
+    }
+
+    let mut winner_turns = Vec::with_capacity(winner_ids.len());
+    for (player_id, earned) in points {
+        // Rando Carlissian doesn't score
+        if player_id <= 0 {
+            continue;
+        }
         let Some(player) = player::Entity::find_by_id(player_id).one(&txn).await? else {
-            return Ok(Ok(()));
+            continue;
         };
 
-        player::ActiveModel {
-            id: ActiveValue::Set(player.id),
-            points: ActiveValue::Set(player.points + 1),
-            ..Default::default()
+        player::Entity::update_many()
+            .col_expr(
+                player::Column::Points,
+                Expr::col(player::Column::Points).add(earned),
+            )
+            .filter(player::Column::Id.eq(player.id))
+            .exec(&txn)
+            .await?;
+
+        if winner_ids.contains(&player.id) {
+            winner_turns.push(player.turn);
+            if chat.global_leaderboard {
+                score::add(&txn, player.telegram_id, 1, 0).await?;
+            }
         }
-        .update(&txn)
-        .await?;
     }
 
-    for hand in hands {
-        hand::ActiveModel {
-            id: ActiveValue::Set(hand.id),
-            won: ActiveValue::Set(true),
-            ..Default::default()
-        }
-        .update(&txn)
-        .await?;
+    for (player_id, wager) in winners {
+        hand::Entity::update_many()
+            .col_expr(hand::Column::Won, Expr::value(true))
+            .filter(
+                hand::Column::ChatId
+                    .eq(chat.id)
+                    .and(hand::Column::PlayedOnTurn.eq(chat.turn))
+                    .and(hand::Column::PlayerId.eq(*player_id))
+                    .and(hand::Column::Wager.eq(*wager)),
+            )
+            .exec(&txn)
+            .await?;
+    }
+
+    let (mut recap, winning) = recap(&txn, chat, winners, &favourites).await?;
+    // achievements are evaluated once per turn, even when void or tied
+    for unlocked in achievement::on_turn(&txn, chat, judge, &winner_ids).await? {
+        recap.push_str("\n\n");
+        recap.push_str(&unlocked);
+    }
+    let mut best = Vec::with_capacity(winning.len());
+    for (player_id, text) in winning {
+        best.push(hall_of_fame::insert(&txn, chat, player_id, text).await?);
     }
 
     let chat = chat::ActiveModel {
         id: ActiveValue::Set(chat.id),
         turn: ActiveValue::Set(chat.turn + 1),
+        // on ties, or when Rando Carlissian wins, judge goes on round-robin
+        judge_turn: ActiveValue::Set(chat.next_judge_turn(match winner_turns[..] {
+            [winner_turn] if winner_ids.len() == 1 => Some(winner_turn),
+            _ => None,
+        })),
         ..Default::default()
     }
     .update(&txn)
@@ -127,6 +393,15 @@ where
     };
     txn.commit().await?;
 
+    let recap = SendMessage::new(chat.telegram_id, recap).with_parse_mode(ParseMode::MarkdownV2);
+    client
+        .execute(if best.is_empty() {
+            recap
+        } else {
+            recap.with_reply_markup(super::best::keyboard(conn, &best).await?)
+        })
+        .await?;
+
     client
         .execute(
             SendMessage::new(chat.telegram_id, msg)
@@ -143,16 +418,133 @@ where
     Ok(Ok(()))
 }
 
+/// builds the turn recap, with winning submissions, the other ones when revealed by chat settings
+/// and the updated scoreboard, returns the winning combinations too
+async fn recap<C>(
+    conn: &C,
+    chat: &chat::Model,
+    winners: &[(i32, bool)],
+    favourites: &[i32],
+) -> Result<(String, Vec<(i32, String)>), Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let stream = player::Entity::find()
+        .filter(player::Column::ChatId.eq(chat.id))
+        .stream(conn)
+        .await?;
+    let mut players = stream
+        .map_ok(|player| (player.id, Cow::Owned(player.tg_link())))
+        .try_collect::<HashMap<_, _>>()
+        .await?;
+    players.insert(0, Cow::Borrowed(crate::RANDO_CARLISSIAN));
+
+    let hands = hand::Entity::find()
+        .filter(
+            hand::Column::ChatId
+                .eq(chat.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn)),
+        )
+        .order_by_asc(hand::Column::Seq)
+        .all(conn)
+        .await?;
+    let stream = card::Entity::find()
+        .filter(card::Column::Id.is_in(hands.iter().map(|hand| hand.card_id)))
+        .stream(conn)
+        .await?;
+    let cards = stream
+        .map_ok(|card| (card.id, card))
+        .try_collect::<HashMap<_, _>>()
+        .await?;
+
+    let mut black_card = None;
+    let mut submissions = BTreeMap::<_, Vec<_>>::new();
+    for hand in &hands {
+        let Some(card) = cards.get(&hand.card_id) else {
+            continue;
+        };
+        if card.color == card::Color::Black {
+            black_card = Some(card);
+        } else {
+            submissions
+                .entry((hand.player_id, hand.wager))
+                .or_default()
+                .push(card);
+        }
+    }
+    let Some(black_card) = black_card else {
+        return Ok((format!("Turn {} is over", chat.turn), Vec::new()));
+    };
+
+    let mut msg = format!("Turn {} recap\n\n{}", chat.turn, black_card.descr());
+    let winning = winners
+        .iter()
+        .filter_map(|winner| {
+            submissions
+                .remove(winner)
+                .map(|winning| (winner.0, black_card.fill(&winning)))
+        })
+        .collect::<Vec<_>>();
+    for (winner_id, winning) in &winning {
+        msg.push_str(&format!(
+            "\n\n🏆 {winning}\n{} wins the turn",
+            players.get(winner_id).map(Cow::as_ref).unwrap_or_default(),
+        ));
+    }
+    if winning.is_empty() {
+        msg.push_str("\n\nNobody wins this turn");
+    }
+    if !favourites.is_empty() {
+        msg.push_str(&format!(
+            "\n\n🗳 People's choice: {}",
+            favourites
+                .iter()
+                .map(|player_id| players.get(player_id).map(Cow::as_ref).unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ));
+    }
+    if chat.reveal_submissions && !submissions.is_empty() {
+        msg.push_str("\n\nOther submissions:");
+        for ((player_id, wager), submission) in submissions {
+            msg.push_str(&format!(
+                "\n{} \\- {}{}",
+                black_card.fill(&submission),
+                players.get(&player_id).map(Cow::as_ref).unwrap_or_default(),
+                if wager { " 🎲" } else { "" },
+            ));
+        }
+    }
+
+    msg.push_str("\n\nScoreboard:");
+    for (position, (points, _, player)) in
+        chat.scoreboard(conn).await?.into_iter().take(3).enumerate()
+    {
+        msg.push_str(&format!("\n{}\\. {player} {points} points", position + 1));
+    }
+
+    Ok((msg, winning))
+}
+
 async fn as_player<C>(
     client: &Client,
     conn: &C,
     player: &player::Model,
     chat: &chat::Model,
-    hand: &hand::Model,
+    hands: &[hand::Model],
 ) -> Result<Result<(), chat::ChatError>, Error>
 where
-    C: ConnectionTrait,
+    C: ConnectionTrait + StreamTrait + TransactionTrait,
 {
+    // stale inline results, a complete answer must fill the whole black card
+    if (hands.len() != 1 && hands.len() != chat.pick as usize)
+        || hands.iter().any(|hand| {
+            hand.player_id != player.id || hand.discarded || hand.played_on_turn.is_some()
+        })
+    {
+        return Ok(Ok(()));
+    }
+
     let played = hand::Entity::find()
         .filter(
             hand::Column::ChatId
@@ -167,36 +559,59 @@ where
         .await?
         .flatten()
         .unwrap_or_default();
-    if played >= chat.pick as i64 {
+    // a point can be wagered on a second submission
+    let wager = played >= chat.pick as i64;
+    if wager && !player.can_wager(chat, played) {
+        return Ok(Ok(()));
+    }
+    // complete answers can't be mixed with a partial one
+    if hands.len() > 1 && played % chat.pick as i64 != 0 {
         return Ok(Ok(()));
     }
 
-    hand::ActiveModel {
-        id: ActiveValue::Set(hand.id),
-        played_on_turn: ActiveValue::Set(Some(chat.turn)),
-        seq: ActiveValue::Set(played as i32),
-        ..Default::default()
+    let txn = conn.begin().await?;
+    for (index, hand) in hands.iter().enumerate() {
+        hand::ActiveModel {
+            id: ActiveValue::Set(hand.id),
+            played_on_turn: ActiveValue::Set(Some(chat.turn)),
+            seq: ActiveValue::Set(((played + index as i64) % chat.pick as i64) as i32),
+            wager: ActiveValue::Set(wager),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
     }
-    .update(conn)
-    .await?;
 
-    let played = hand::Entity::find()
-        .filter(
-            hand::Column::ChatId
-                .eq(chat.id)
-                .and(hand::Column::PlayedOnTurn.eq(Some(chat.turn)))
-                .and(hand::Column::PlayerId.gt(0)),
-        )
-        .select_only()
-        .column_as(hand::Column::Id.count(), "count")
-        .into_tuple::<Option<i64>>()
-        .one(conn)
-        .await?
-        .flatten()
-        .unwrap_or_default();
+    // the point is wagered with the first card
+    if played == chat.pick as i64 {
+        player::Entity::update_many()
+            .col_expr(
+                player::Column::Points,
+                Expr::col(player::Column::Points).sub(1),
+            )
+            .filter(player::Column::Id.eq(player.id))
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+
+    // players are already waiting for the judge
+    if wager {
+        return Ok(Ok(()));
+    }
+
+    let all_played = chat.all_played(conn).await?;
 
-    // judge always plays only 1 card
-    if played > (chat.players as i64 - 1) * chat.pick as i64 {
+    // everyone votes the winner
+    if chat.god_is_dead {
+        if all_played {
+            super::vote::send(client, conn, chat).await?;
+        }
+
+        return Ok(Ok(()));
+    }
+
+    if all_played {
         let Some(judge) = player::Entity::find()
             .filter(
                 player::Column::ChatId
@@ -210,9 +625,14 @@ where
         };
 
         let msg = format!(
-            "All players have choosen their card{}, now {} can choose the winner",
+            "All players have choosen their card{}, now {} can {}",
             if chat.pick > 1 { "s" } else { "" },
-            judge.tg_link()
+            judge.tg_link(),
+            if chat.survival {
+                "start eliminating the weakest submissions"
+            } else {
+                "choose the winner"
+            }
         );
 
         client
@@ -227,6 +647,10 @@ where
                     .with_parse_mode(ParseMode::MarkdownV2),
             )
             .await?;
+
+        if chat.peoples_choice {
+            super::vote::send(client, conn, chat).await?;
+        }
     }
 
     Ok(Ok(()))
@@ -0,0 +1,174 @@
+use futures_util::{stream, TryStreamExt};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter,
+    StreamTrait, TransactionTrait,
+};
+use tgbot::{
+    api::Client,
+    types::{
+        AnswerInlineQuery, InlineKeyboardButton, InlineQueryResult, InlineQueryResultArticle,
+        InputMessageContentText, ParseMode, SendMessage, User,
+    },
+};
+
+use tracing::warn;
+
+use super::play::{split_multiline_cards, PlayError};
+use crate::{
+    entities::{card, chat, hand, player},
+    Error,
+};
+
+/// lists the cards that can be discarded, from the inline query `{chat_id} discard`
+pub async fn inline<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    query_id: &str,
+    chat: &chat::Model,
+) -> Result<Result<(), PlayError>, Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    if chat.end_date.is_some() {
+        return Ok(Err(PlayError::GameEnded));
+    }
+
+    if !chat.started {
+        return Ok(Err(PlayError::NotStarted));
+    }
+
+    let Some(player) = player::Entity::find()
+        .filter(
+            player::Column::TelegramId
+                .eq(i64::from(user.id))
+                .and(player::Column::ChatId.eq(chat.id)),
+        )
+        .one(conn)
+        .await?
+    else {
+        return Ok(Err(PlayError::PlayerNotFound));
+    };
+
+    let left = chat.discards - player.discards;
+    if left <= 0 {
+        return Ok(Err(PlayError::NoDiscards));
+    }
+
+    let hands = hand::Entity::find()
+        .filter(
+            hand::Column::PlayerId
+                .eq(player.id)
+                .and(hand::Column::PlayedOnTurn.is_null())
+                .and(hand::Column::Discarded.eq(false)),
+        )
+        .all(conn)
+        .await?;
+
+    let stream = card::Entity::find()
+        .filter(card::Column::Id.is_in(hands.iter().map(|hand| hand.card_id)))
+        .stream(conn)
+        .await?;
+    let cards = stream
+        .map_ok(|card| {
+            let hand_id = hands
+                .iter()
+                .find(|hand| hand.card_id == card.id)
+                .map(|hand| hand.id)
+                .unwrap_or_default();
+            let lines = split_multiline_cards(card.text(), format!("discard {hand_id}"));
+
+            stream::iter(lines.into_iter().map(move |(id, text)| {
+                Ok::<_, DbErr>(InlineQueryResult::Article(
+                    InlineQueryResultArticle::new(
+                        id,
+                        InputMessageContentText::new(format!(
+                            "I've discarded a card, {} discard{} left",
+                            left - 1,
+                            if left - 1 == 1 { "" } else { "s" }
+                        )),
+                        text,
+                    )
+                    .with_reply_markup([[
+                        InlineKeyboardButton::for_switch_inline_query_current_chat(
+                            "Open cards hand",
+                            chat.id.to_string(),
+                        ),
+                    ]]),
+                ))
+            }))
+        })
+        .try_flatten()
+        .try_collect::<Vec<_>>()
+        .await?;
+
+    client
+        .execute(AnswerInlineQuery::new(query_id, cards).with_cache_time(0))
+        .await?;
+
+    Ok(Ok(()))
+}
+
+/// throws away a card and draws a replacement
+pub async fn execute<C>(client: &Client, conn: &C, user: &User, hand_id: i32) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait + TransactionTrait,
+{
+    let Some(hand) = hand::Entity::find_by_id(hand_id).one(conn).await? else {
+        return Ok(());
+    };
+    if hand.discarded || hand.played_on_turn.is_some() {
+        return Ok(());
+    }
+
+    let Some(chat) = chat::Entity::find_by_id(hand.chat_id).one(conn).await? else {
+        return Ok(());
+    };
+    if chat.end_date.is_some() || !chat.started {
+        return Ok(());
+    }
+
+    let Some(player) = player::Entity::find_by_id(hand.player_id).one(conn).await? else {
+        return Ok(());
+    };
+    if player.telegram_id != i64::from(user.id) || player.discards >= chat.discards {
+        return Ok(());
+    }
+
+    let txn = conn.begin().await?;
+
+    hand::ActiveModel {
+        id: ActiveValue::Set(hand.id),
+        discarded: ActiveValue::Set(true),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await?;
+
+    player::ActiveModel {
+        id: ActiveValue::Set(player.id),
+        discards: ActiveValue::Set(player.discards + 1),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await?;
+
+    // when there are no cards left the discard is rolled back and the hand kept as it is
+    if let Err(e) = hand::pick(&txn, player.id, chat.id, chat.turn, false).await? {
+        warn!("Can't replace discarded card: {e}");
+        client
+            .execute(
+                SendMessage::new(
+                    chat.telegram_id,
+                    format!("{} can't discard a card: {e}", player.tg_link()),
+                )
+                .with_parse_mode(ParseMode::MarkdownV2),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    txn.commit().await?;
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::{collections::HashMap, io::Cursor};
+
+use chrono::NaiveDateTime;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, StreamTrait};
+use serde::Serialize;
+use tgbot::{
+    api::Client,
+    types::{InputFile, InputFileReader, SendDocument},
+};
+
+use crate::{
+    entities::{chat, player},
+    Error,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum ExportError {
+    #[error("There are no played turns to export")]
+    NoTurns,
+}
+
+#[derive(Serialize)]
+struct Transcript<'a> {
+    game: i32,
+    start_date: NaiveDateTime,
+    end_date: Option<NaiveDateTime>,
+    turns: Vec<Turn<'a>>,
+}
+
+#[derive(Serialize)]
+struct Turn<'a> {
+    turn: i32,
+    black_card: &'a str,
+    judge: &'a str,
+    submissions: Vec<Submission<'a>>,
+    /// more than one on ties
+    winners: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Submission<'a> {
+    author: &'a str,
+    cards: Vec<PlayedCard<'a>>,
+    won: bool,
+    wager: bool,
+}
+
+#[derive(Serialize)]
+struct PlayedCard<'a> {
+    seq: i32,
+    text: &'a str,
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    chat: &chat::Model,
+) -> Result<Result<(), ExportError>, Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    // current game, or the last closed one if it hasn't been played yet
+    let game = if chat.turn > 1 {
+        Some(chat.clone())
+    } else {
+        chat::Entity::find()
+            .filter(
+                chat::Column::TelegramId
+                    .eq(chat.telegram_id)
+                    .and(chat::Column::EndDate.is_not_null()),
+            )
+            .order_by_desc(chat::Column::EndDate)
+            .one(conn)
+            .await?
+    };
+    let Some(game) = game.filter(|game| game.turn > 1) else {
+        return Ok(Err(ExportError::NoTurns));
+    };
+
+    let players = player::Entity::find()
+        .filter(player::Column::ChatId.eq(game.id))
+        .all(conn)
+        .await?;
+    let names = players
+        .iter()
+        .map(|player| (player.id, player.plain_name()))
+        .chain([(0, crate::RANDO_CARLISSIAN)])
+        .collect::<HashMap<_, _>>();
+    let name = |player_id: &i32| names.get(player_id).copied().unwrap_or_default();
+
+    let rounds = game.rounds(conn).await?;
+    let transcript = Transcript {
+        game: game.id,
+        start_date: game.start_date,
+        end_date: game.end_date,
+        turns: rounds
+            .iter()
+            .filter_map(|round| {
+                let black_card = round.black_card.as_ref()?;
+                let submissions = round
+                    .submissions
+                    .iter()
+                    .map(|((player_id, wager), submission)| Submission {
+                        author: name(player_id),
+                        cards: submission
+                            .iter()
+                            .map(|(hand, card)| PlayedCard {
+                                seq: hand.seq,
+                                text: card.plain_text(),
+                            })
+                            .collect(),
+                        won: submission.iter().any(|(hand, _)| hand.won),
+                        wager: *wager,
+                    })
+                    .collect::<Vec<_>>();
+                Some(Turn {
+                    turn: round.turn,
+                    black_card: black_card.plain_text(),
+                    judge: round.judge.as_ref().map(name).unwrap_or_default(),
+                    winners: submissions
+                        .iter()
+                        .filter(|s| s.won)
+                        .map(|s| s.author)
+                        .collect(),
+                    submissions,
+                })
+            })
+            .collect(),
+    };
+
+    let json = serde_json::to_vec_pretty(&transcript)?;
+    let markdown = to_markdown(&transcript);
+
+    for (content, extension) in [(markdown.into_bytes(), "md"), (json, "json")] {
+        client
+            .execute(SendDocument::new(
+                chat.telegram_id,
+                InputFile::from(
+                    InputFileReader::new(Cursor::new(content))
+                        .with_file_name(format!("game-{}.{extension}", game.id)),
+                ),
+            ))
+            .await?;
+    }
+
+    Ok(Ok(()))
+}
+
+fn to_markdown(transcript: &Transcript) -> String {
+    let mut out = format!(
+        "# Game {}\n\nStarted on {}",
+        transcript.game,
+        transcript.start_date.format("%Y-%m-%d %H:%M")
+    );
+    if let Some(end_date) = transcript.end_date {
+        out.push_str(&format!(", ended on {}", end_date.format("%Y-%m-%d %H:%M")));
+    }
+    out.push('\n');
+
+    for turn in &transcript.turns {
+        out.push_str(&format!(
+            "\n## Turn {}\n\n**{}**\n\nJudge: {}\n\n",
+            turn.turn, turn.black_card, turn.judge
+        ));
+        for submission in &turn.submissions {
+            out.push_str(&format!(
+                "- {}{}{}: {}\n",
+                if submission.won { "🏆 " } else { "" },
+                submission.author,
+                if submission.wager { " (wager)" } else { "" },
+                submission
+                    .cards
+                    .iter()
+                    .map(|card| format!("{}. {}", card.seq + 1, card.text))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ));
+        }
+    }
+
+    out
+}
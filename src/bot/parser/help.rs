@@ -18,16 +18,32 @@ pub async fn execute(
                 format!(
                     "[Cards Against Humanity Bot](https://github\\.com/nappa85/cah\\_bot/)
 
+/alltime \\- show the ranking of all closed games in this chat
+/badges \\- show your achievements
+/begin \\- begin the game once everyone joined
+/best \\- show the best winning combinations of this chat
+/cardstats \\- show cards and packs win rates in this chat
 /close \\- close the game and get a winner
+/export \\- download the current or last game transcript
 /help \\- this message
-/start \\- start or join the game in this chat
+/history \\- list previous games in this chat
+/me \\- show your stats across every chat
+/reboot \\- trade a point for a new hand, if enabled
+/redraw \\- veto the black card once per turn, before anyone played, if you're the judge
+/rematch \\- start a new game with the same players and settings of the previous one
+/start \\- create or join the game in this chat
+/season \\- show current season standings
 /settings \\- change game setting
 /status \\- show game status
 /rank \\- show players ranking
+/top \\- show the global leaderboard of this season, use `/top week` for this week only or `/top all` for all time
+/ratings \\- show global players ratings
 
-To view you hand and choose a card for this game use the inline command `{bot_name} {}`
+To view you hand and choose a card for this game use the inline command `{bot_name} {chat_id}`
+When the black card needs more answers, complete ones are listed after your cards and are played in the shown order
+To discard a card, if allowed, use the inline command `{bot_name} {chat_id} discard`
                 ",
-                    chat.id
+                    chat_id = chat.id
                 ),
             )
             .with_reply_parameters(ReplyParameters::new(message_id))
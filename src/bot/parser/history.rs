@@ -0,0 +1,287 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, StreamTrait};
+use tgbot::{
+    api::Client,
+    types::{EditMessageText, InlineKeyboardButton, ParseMode, ReplyParameters, SendMessage},
+};
+
+use crate::{
+    entities::{chat, player},
+    utils::escape_markdown,
+    Error,
+};
+
+const GAMES_PER_PAGE: usize = 5;
+const ROUNDS_PER_PAGE: usize = 10;
+
+#[derive(thiserror::Error, Debug)]
+pub enum HistoryError {
+    #[error("There are no closed games in this chat")]
+    NoGames,
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    message_id: i64,
+    chat: &chat::Model,
+    query_data: Option<&str>,
+) -> Result<Result<(), HistoryError>, Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let games = chat::Entity::find()
+        .filter(
+            chat::Column::TelegramId
+                .eq(chat.telegram_id)
+                .and(chat::Column::EndDate.is_not_null()),
+        )
+        .order_by_asc(chat::Column::StartDate)
+        .all(conn)
+        .await?;
+    if games.is_empty() {
+        return Ok(Err(HistoryError::NoGames));
+    }
+
+    let mut args = query_data.unwrap_or_default().split_whitespace().skip(1);
+    let (msg, keyboard) = match (args.next(), args.next(), args.next(), args.next()) {
+        (Some("game"), Some(game_id), start, page) => {
+            let Some(index) = game_id
+                .parse::<i32>()
+                .ok()
+                .and_then(|game_id| games.iter().position(|game| game.id == game_id))
+            else {
+                return Ok(Ok(()));
+            };
+            game(
+                conn,
+                index,
+                &games[index],
+                start.and_then(|s| s.parse().ok()).unwrap_or_default(),
+                page.and_then(|s| s.parse().ok()).unwrap_or_default(),
+            )
+            .await?
+        }
+        (Some("page"), page, _, _) => {
+            list(
+                conn,
+                &games,
+                page.and_then(|s| s.parse().ok()).unwrap_or_default(),
+            )
+            .await?
+        }
+        _ => list(conn, &games, 0).await?,
+    };
+
+    if query_data.is_none() {
+        client
+            .execute(
+                SendMessage::new(chat.telegram_id, msg)
+                    .with_reply_parameters(ReplyParameters::new(message_id))
+                    .with_reply_markup(keyboard)
+                    .with_parse_mode(ParseMode::MarkdownV2),
+            )
+            .await?;
+    } else {
+        client
+            .execute(
+                EditMessageText::for_chat_message(chat.telegram_id, message_id, msg)
+                    .with_reply_markup(keyboard)
+                    .with_parse_mode(ParseMode::MarkdownV2),
+            )
+            .await?;
+    }
+
+    Ok(Ok(()))
+}
+
+/// games list page
+async fn list<C>(
+    conn: &C,
+    games: &[chat::Model],
+    page: usize,
+) -> Result<(String, Vec<Vec<InlineKeyboardButton>>), Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let pages = games.len().div_ceil(GAMES_PER_PAGE);
+    let page = page.min(pages - 1);
+    let start = page * GAMES_PER_PAGE;
+    let games = &games[start..games.len().min(start + GAMES_PER_PAGE)];
+
+    let chat_ids = games.iter().map(|game| game.id).collect::<Vec<_>>();
+    let names = names(conn, &chat_ids).await?;
+    let standings = chat::standings(conn, &chat_ids).await?;
+
+    let mut msg = format!("Games history, page {}/{pages}", page + 1);
+    let mut keyboard = Vec::with_capacity(GAMES_PER_PAGE + 1);
+    for (index, game) in games.iter().enumerate() {
+        let number = start + index + 1;
+        msg.push_str(&format!(
+            "\n\n*Game {number}*\nFrom {} to {}, {} turns",
+            escape_markdown(game.start_date.format("%Y-%m-%d %H:%M").to_string()),
+            escape_markdown(
+                game.end_date
+                    .unwrap_or_default()
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            ),
+            game.turn - 1,
+        ));
+
+        let standings = standings
+            .get(&game.id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let winners = chat::winners(standings)
+            .filter_map(|telegram_id| names.get(&(game.id, telegram_id)))
+            .map(Cow::as_ref)
+            .collect::<Vec<_>>();
+        if !winners.is_empty() {
+            msg.push_str(&format!(
+                "\nWinner{} {}",
+                if winners.len() > 1 { "s" } else { "" },
+                winners.join(" and ")
+            ));
+        }
+        msg.push_str(&format!(
+            "\nScores: {}",
+            standings
+                .iter()
+                .filter_map(|(telegram_id, points)| names
+                    .get(&(game.id, *telegram_id))
+                    .map(|name| format!("{name} {points}")))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!("Game {number} turns"),
+            format!("history game {} 0 {page}", game.id),
+        )]);
+    }
+
+    let mut buttons = Vec::new();
+    if page > 0 {
+        buttons.push(InlineKeyboardButton::for_callback_data(
+            "<<",
+            format!("history page {}", page - 1),
+        ));
+    }
+    if page + 1 < pages {
+        buttons.push(InlineKeyboardButton::for_callback_data(
+            ">>",
+            format!("history page {}", page + 1),
+        ));
+    }
+    keyboard.push(buttons);
+
+    Ok((msg, keyboard))
+}
+
+/// round by round winners of a single game
+async fn game<C>(
+    conn: &C,
+    index: usize,
+    game: &chat::Model,
+    start: usize,
+    page: usize,
+) -> Result<(String, Vec<Vec<InlineKeyboardButton>>), Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let names = player::Entity::find()
+        .filter(player::Column::ChatId.eq(game.id))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|player| (player.id, Cow::Owned(player.tg_link())))
+        .chain([(0, Cow::Borrowed(crate::RANDO_CARLISSIAN))])
+        .collect::<HashMap<_, _>>();
+
+    let rounds = game.rounds(conn).await?;
+    let start = start.min(rounds.len().saturating_sub(1));
+
+    let mut msg = format!("*Game {}*", index + 1);
+    for round in rounds.iter().skip(start).take(ROUNDS_PER_PAGE) {
+        let Some(black_card) = &round.black_card else {
+            continue;
+        };
+        msg.push_str(&format!("\n\nTurn {}\n", round.turn));
+        let winners = round.winners();
+        if winners.is_empty() {
+            msg.push_str(&black_card.descr());
+        }
+        for (index, (player_id, cards)) in winners.into_iter().enumerate() {
+            if index > 0 {
+                msg.push('\n');
+            }
+            msg.push_str(&format!(
+                "{}\nwon by {}",
+                black_card.fill(&cards),
+                names.get(&player_id).map(Cow::as_ref).unwrap_or_default()
+            ));
+        }
+    }
+
+    let mut buttons = Vec::new();
+    if start > 0 {
+        buttons.push(InlineKeyboardButton::for_callback_data(
+            "<<",
+            format!(
+                "history game {} {} {page}",
+                game.id,
+                start.saturating_sub(ROUNDS_PER_PAGE)
+            ),
+        ));
+    }
+    if start + ROUNDS_PER_PAGE < rounds.len() {
+        buttons.push(InlineKeyboardButton::for_callback_data(
+            ">>",
+            format!(
+                "history game {} {} {page}",
+                game.id,
+                start + ROUNDS_PER_PAGE
+            ),
+        ));
+    }
+
+    Ok((
+        msg,
+        vec![
+            buttons,
+            vec![InlineKeyboardButton::for_callback_data(
+                "Back to games",
+                format!("history page {page}"),
+            )],
+        ],
+    ))
+}
+
+/// player names by game id and Telegram user id
+async fn names<C>(
+    conn: &C,
+    chat_ids: &[i32],
+) -> Result<HashMap<(i32, i64), Cow<'static, str>>, Error>
+where
+    C: ConnectionTrait,
+{
+    Ok(player::Entity::find()
+        .filter(player::Column::ChatId.is_in(chat_ids.iter().copied()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|player| {
+            (
+                (player.chat_id, player.telegram_id),
+                Cow::Owned(player.tg_link()),
+            )
+        })
+        .chain(
+            chat_ids
+                .iter()
+                .map(|chat_id| ((*chat_id, 0), Cow::Borrowed(crate::RANDO_CARLISSIAN))),
+        )
+        .collect())
+}
@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, StreamTrait};
+use tgbot::{
+    api::Client,
+    types::{ParseMode, ReplyParameters, SendMessage, User},
+};
+
+use crate::{
+    entities::{card, chat, hand, player},
+    Error,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum MeError {
+    #[error("You never played a game")]
+    NeverPlayed,
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    message_id: i64,
+    chat: &chat::Model,
+) -> Result<Result<(), MeError>, Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let telegram_id = i64::from(user.id);
+    let players = player::Entity::find()
+        .filter(player::Column::TelegramId.eq(telegram_id))
+        .order_by_desc(player::Column::Id)
+        .all(conn)
+        .await?;
+    let Some(last) = players.first() else {
+        return Ok(Err(MeError::NeverPlayed));
+    };
+
+    let closed = chat::Entity::find()
+        .filter(
+            chat::Column::Id
+                .is_in(players.iter().map(|player| player.chat_id))
+                .and(chat::Column::EndDate.is_not_null()),
+        )
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|chat| chat.id)
+        .collect::<Vec<_>>();
+    let games_won = chat::standings(conn, &closed)
+        .await?
+        .values()
+        .filter(|standings| chat::winners(standings).any(|id| id == telegram_id))
+        .count();
+
+    // every stat comes from closed games only
+    let player_ids = players
+        .iter()
+        .filter(|player| closed.contains(&player.chat_id))
+        .map(|player| player.id)
+        .collect::<Vec<_>>();
+    let won = hand::Entity::find()
+        .filter(
+            hand::Column::PlayerId
+                .is_in(player_ids)
+                .and(hand::Column::Won.eq(true)),
+        )
+        .all(conn)
+        .await?;
+    // multi-pick answers have a winning hand per card
+    let rounds_won = won
+        .iter()
+        .map(|hand| (hand.player_id, hand.played_on_turn))
+        .collect::<HashSet<_>>()
+        .len();
+
+    // the white card this user won more turns with
+    let favourite = won
+        .iter()
+        .fold(HashMap::<_, usize>::new(), |mut cards, hand| {
+            *cards.entry(hand.card_id).or_default() += 1;
+            cards
+        })
+        .into_iter()
+        .max_by_key(|(card_id, count)| (*count, -card_id))
+        .map(|(card_id, _)| card_id);
+    let favourite = match favourite {
+        Some(card_id) => card::Entity::find_by_id(card_id).one(conn).await?,
+        None => None,
+    };
+
+    let msg = format!(
+        "{} stats\n\nGames played: {}\nGames won: {games_won}\nRounds won: {rounds_won}\nWin rate: {}%{}",
+        last.tg_link(),
+        closed.len(),
+        if closed.is_empty() {
+            0
+        } else {
+            games_won * 100 / closed.len()
+        },
+        favourite
+            .map(|card| format!("\nFavourite winning card: *{}*", card.text()))
+            .unwrap_or_default(),
+    );
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(Ok(()))
+}
@@ -10,18 +10,35 @@ use tgbot::{
 };
 
 use crate::{
-    entities::{chat, hand},
+    entities::{chat, hand, season::Period},
     Error,
 };
 
+mod alltime;
+mod badges;
+mod begin;
+mod best;
+mod cardstats;
 mod choose;
 mod close;
+mod discard;
+mod export;
 mod help;
+mod history;
+mod me;
 mod play;
 mod rank;
+mod ratings;
+mod reboot;
+mod redraw;
+mod rematch;
+mod retract;
+mod season;
 mod settings;
 mod start;
 mod status;
+mod top;
+mod vote;
 
 #[derive(thiserror::Error, Debug)]
 enum BotError {
@@ -32,17 +49,37 @@ enum BotError {
     #[error(transparent)]
     Start(#[from] start::StartError),
     #[error(transparent)]
+    Begin(#[from] begin::BeginError),
+    #[error(transparent)]
     Settings(#[from] settings::SettingsError),
     #[error(transparent)]
     Status(#[from] status::StatusError),
     #[error(transparent)]
     Close(#[from] close::CloseError),
+    #[error(transparent)]
+    Rematch(#[from] rematch::RematchError),
+    #[error(transparent)]
+    Alltime(#[from] alltime::AlltimeError),
+    #[error(transparent)]
+    Me(#[from] me::MeError),
+    #[error(transparent)]
+    History(#[from] history::HistoryError),
+    #[error(transparent)]
+    Export(#[from] export::ExportError),
+    #[error(transparent)]
+    Best(#[from] best::BestError),
+    #[error(transparent)]
+    Reboot(#[from] reboot::RebootError),
+    #[error(transparent)]
+    Redraw(#[from] redraw::RedrawError),
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn parse_message<C>(
     client: &Client,
     conn: &C,
     name: &str,
+    period: Period,
     user: &User,
     message_id: i64,
     msg: &str,
@@ -59,6 +96,15 @@ where
                 Some("/start") => start::execute(client, conn, user, message_id, &chat)
                     .await?
                     .map_err(BotError::from),
+                Some("/badges") => {
+                    Ok(badges::execute(client, conn, user, message_id, &chat).await?)
+                }
+                Some("/begin") => begin::execute(client, conn, user, message_id, &chat)
+                    .await?
+                    .map_err(BotError::from),
+                Some("/season") => {
+                    Ok(season::execute(client, conn, message_id, &chat, period).await?)
+                }
                 Some("/settings") => settings::execute(client, conn, user, message_id, &chat, None)
                     .await?
                     .map_err(BotError::from),
@@ -66,9 +112,45 @@ where
                     .await?
                     .map_err(BotError::from),
                 Some("/rank") => Ok(rank::execute(client, conn, message_id, &chat).await?),
+                Some("/alltime") => alltime::execute(client, conn, message_id, &chat)
+                    .await?
+                    .map_err(BotError::from),
+                Some("/export") => export::execute(client, conn, &chat)
+                    .await?
+                    .map_err(BotError::from),
+                Some("/history") => history::execute(client, conn, message_id, &chat, None)
+                    .await?
+                    .map_err(BotError::from),
+                Some("/me") => me::execute(client, conn, user, message_id, &chat)
+                    .await?
+                    .map_err(BotError::from),
+                Some("/best") => best::execute(client, conn, message_id, &chat)
+                    .await?
+                    .map_err(BotError::from),
+                Some("/ratings") => Ok(ratings::execute(client, conn, message_id, &chat).await?),
+                Some("/cardstats") => {
+                    Ok(cardstats::execute(client, conn, message_id, &chat).await?)
+                }
+                Some("/top") => Ok(top::execute(
+                    client,
+                    conn,
+                    message_id,
+                    &chat,
+                    top::Window::parse(iter.next(), period),
+                )
+                .await?),
                 Some("/close") => close::execute(client, conn, user, message_id, &chat)
                     .await?
                     .map_err(BotError::from),
+                Some("/reboot") => reboot::execute(client, conn, user, message_id, &chat)
+                    .await?
+                    .map_err(BotError::from),
+                Some("/redraw") => redraw::execute(client, conn, user, Some(message_id), &chat)
+                    .await?
+                    .map_err(BotError::from),
+                Some("/rematch") => rematch::execute(client, conn, user, message_id, &chat)
+                    .await?
+                    .map_err(BotError::from),
                 _ => return Ok(()),
             }
         }
@@ -88,6 +170,13 @@ where
     Ok(())
 }
 
+pub async fn season_rollover<C>(client: &Client, conn: &C, period: Period) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait + TransactionTrait,
+{
+    season::rollover(client, conn, period).await
+}
+
 pub async fn parse_callback_query<C>(
     client: &Client,
     conn: &C,
@@ -104,6 +193,17 @@ where
     };
 
     let res = match chat::find_or_insert(conn, tg_chat).await? {
+        Ok(chat) if data.starts_with("best") => {
+            Ok(best::vote(client, conn, user, message_id, &chat, data).await?)
+        }
+        Ok(chat) if data.starts_with("vote") => {
+            Ok(vote::execute(client, conn, user, message_id, &chat, data).await?)
+        }
+        Ok(chat) if data.starts_with("history") => {
+            history::execute(client, conn, message_id, &chat, Some(data))
+                .await?
+                .map_err(BotError::from)
+        }
         Ok(chat) => settings::execute(client, conn, user, message_id, &chat, Some(data))
             .await?
             .map_err(BotError::from),
@@ -129,11 +229,12 @@ pub async fn parse_inline_query<C>(
     user: &User,
     query_id: &str,
     msg: &str,
+    offset: &str,
 ) -> Result<(), Error>
 where
     C: ConnectionTrait + StreamTrait + TransactionTrait,
 {
-    if let Err(err) = parse_inline_query_inner(client, conn, user, query_id, msg).await? {
+    if let Err(err) = parse_inline_query_inner(client, conn, user, query_id, msg, offset).await? {
         client
             .execute(AnswerInlineQuery::new(query_id, err).with_cache_time(0))
             .await?;
@@ -148,18 +249,24 @@ async fn parse_inline_query_inner<C>(
     user: &User,
     query_id: &str,
     msg: &str,
+    offset: &str,
 ) -> Result<Result<(), play::PlayError>, Error>
 where
     C: ConnectionTrait + StreamTrait + TransactionTrait,
 {
-    let Ok(chat_id) = msg.parse::<i32>() else {
+    let mut iter = msg.split_whitespace();
+    let Some(Ok(chat_id)) = iter.next().map(str::parse::<i32>) else {
         return Ok(Err(play::PlayError::Clear));
     };
     let Some(chat) = chat::Entity::find_by_id(chat_id).one(conn).await? else {
         return Ok(Err(play::PlayError::Clear));
     };
 
-    play::execute(client, conn, user, query_id, &chat).await
+    if iter.next() == Some("discard") {
+        discard::inline(client, conn, user, query_id, &chat).await
+    } else {
+        play::execute(client, conn, user, query_id, offset, &chat).await
+    }
 }
 
 pub async fn parse_inline_query_response<C>(
@@ -171,11 +278,71 @@ pub async fn parse_inline_query_response<C>(
 where
     C: ConnectionTrait + StreamTrait + TransactionTrait,
 {
-    // remove anything after a ';' then split it by whitespace and convert to i32
-    let Ok(hand_ids) = result_id
+    // remove anything after a ';'
+    let result_id = result_id
         .split_once(';')
         .map(|(s, _)| s)
-        .unwrap_or(result_id)
+        .unwrap_or(result_id);
+    if let Some(chat_id) = result_id.strip_prefix("redraw ") {
+        let Ok(chat_id) = chat_id.parse::<i32>() else {
+            return Ok(());
+        };
+        let Some(chat) = chat::Entity::find_by_id(chat_id).one(conn).await? else {
+            return Ok(());
+        };
+        // errors are already shown in the inline results
+        let _ = redraw::execute(client, conn, user, None, &chat).await?;
+        return Ok(());
+    }
+    if let Some(chat_id) = result_id.strip_prefix("void ") {
+        return match chat_id.parse() {
+            Ok(chat_id) => choose::verdict(client, conn, user, chat_id, &[]).await,
+            Err(_) => Ok(()),
+        };
+    }
+    if let Some(tie) = result_id.strip_prefix("tie ") {
+        // submissions are identified by player id, with a trailing 'w' when wagered
+        let Some((Ok(chat_id), submissions)) = tie
+            .split_once(' ')
+            .map(|(chat_id, submissions)| (chat_id.parse::<i32>(), submissions))
+        else {
+            return Ok(());
+        };
+        let Ok(winners) = submissions
+            .split(',')
+            .map(|submission| match submission.strip_suffix('w') {
+                Some(player_id) => player_id.parse::<i32>().map(|player_id| (player_id, true)),
+                None => submission
+                    .parse::<i32>()
+                    .map(|player_id| (player_id, false)),
+            })
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            return Ok(());
+        };
+        return choose::verdict(client, conn, user, chat_id, &winners).await;
+    }
+    if let Some(chat_id) = result_id.strip_prefix("withdraw ") {
+        return match chat_id.parse() {
+            Ok(chat_id) => retract::withdraw(conn, user, chat_id).await,
+            Err(_) => Ok(()),
+        };
+    }
+    if let Some(hand_id) = result_id.strip_prefix("first ") {
+        return match hand_id.parse() {
+            Ok(hand_id) => retract::reorder(conn, user, hand_id).await,
+            Err(_) => Ok(()),
+        };
+    }
+    if let Some(hand_id) = result_id.strip_prefix("discard ") {
+        return match hand_id.parse() {
+            Ok(hand_id) => discard::execute(client, conn, user, hand_id).await,
+            Err(_) => Ok(()),
+        };
+    }
+
+    // split it by whitespace and convert to i32
+    let Ok(hand_ids) = result_id
         .split_whitespace()
         .map(|s| s.parse::<i32>())
         .collect::<Result<Vec<_>, _>>()
@@ -187,13 +354,15 @@ where
     }
 
     let len = hand_ids.len();
-    let hands = hand::Entity::find()
-        .filter(hand::Column::Id.is_in(hand_ids))
+    let mut hands = hand::Entity::find()
+        .filter(hand::Column::Id.is_in(hand_ids.iter().copied()))
         .all(conn)
         .await?;
     if hands.len() != len {
         return Ok(());
     }
+    // complete answers are played in the chosen order
+    hands.sort_by_key(|hand| hand_ids.iter().position(|id| *id == hand.id));
 
     choose::execute(client, conn, user, &hands).await
 }
@@ -1,4 +1,4 @@
-use std::{borrow::Cow, collections::HashMap, future, option::IntoIter};
+use std::{borrow::Cow, collections::HashMap, future, iter, option::IntoIter};
 
 use futures_util::{stream, TryStreamExt};
 use rand::Rng;
@@ -103,6 +103,8 @@ pub enum PlayError {
     Clear,
     #[error("🛑 This game have been closed")]
     GameEnded,
+    #[error("⏳ This game hasn't begun yet")]
+    NotStarted,
     #[error("⚠️ Not enough players in the game")]
     NotEnoughPlayers,
     #[error("⛔ You're not part of this game, use /start to join")]
@@ -113,6 +115,8 @@ pub enum PlayError {
     NotJudgeTurn,
     #[error("⌛ You already played this turn")]
     AlreadyPlayed,
+    #[error("🚫 You have no discards left in this game")]
+    NoDiscards,
 }
 
 impl IntoIterator for PlayError {
@@ -140,6 +144,7 @@ pub async fn execute<C>(
     conn: &C,
     user: &User,
     query_id: &str,
+    offset: &str,
     chat: &chat::Model,
 ) -> Result<Result<(), PlayError>, Error>
 where
@@ -149,6 +154,10 @@ where
         return Ok(Err(PlayError::GameEnded));
     }
 
+    if !chat.started {
+        return Ok(Err(PlayError::NotStarted));
+    }
+
     // rando carlissian counts as a player
     if 3 > chat.players + chat.rando_carlissian as i32 {
         return Ok(Err(PlayError::NotEnoughPlayers));
@@ -168,7 +177,7 @@ where
 
     // when you're the judge
     if player.is_my_turn(chat) {
-        as_judge(client, conn, &player, query_id, chat).await
+        as_judge(client, conn, &player, query_id, offset, chat).await
     } else {
         as_player(client, conn, &player, query_id, chat).await
     }
@@ -179,6 +188,7 @@ async fn as_judge<C>(
     conn: &C,
     player: &player::Model,
     query_id: &str,
+    offset: &str,
     chat: &chat::Model,
 ) -> Result<Result<(), PlayError>, Error>
 where
@@ -192,15 +202,30 @@ where
         )
         .stream(conn)
         .await?;
+    // players links and plain names
     let mut players = stream
-        .map_ok(|player| (player.id, Cow::Owned(player.tg_link())))
+        .map_ok(|player| {
+            (
+                player.id,
+                (
+                    Cow::Owned(player.tg_link()),
+                    Cow::Owned(player.plain_name().to_owned()),
+                ),
+            )
+        })
         .try_collect::<HashMap<_, _>>()
         .await?;
     if players.is_empty() {
         return Ok(Err(PlayError::NotEnoughPlayers));
     }
     if chat.rando_carlissian {
-        players.insert(0, Cow::Borrowed(crate::RANDO_CARLISSIAN));
+        players.insert(
+            0,
+            (
+                Cow::Borrowed(crate::RANDO_CARLISSIAN),
+                Cow::Borrowed(crate::RANDO_CARLISSIAN),
+            ),
+        );
     }
 
     let stream = hand::Entity::find()
@@ -212,14 +237,15 @@ where
         .order_by_asc(hand::Column::Seq)
         .stream(conn)
         .await?;
-    let (judge_card, hands) = stream
+    let (judge_card, mut hands) = stream
         .try_fold(
             (None, HashMap::with_capacity(players.len())),
             |(mut judge_card, mut hands), hand| {
                 if hand.player_id == player.id {
                     judge_card = Some(hand.card_id);
                 } else {
-                    let player: &mut Vec<_> = hands.entry(hand.player_id).or_default();
+                    let player: &mut Vec<_> =
+                        hands.entry((hand.player_id, hand.wager)).or_default();
                     player.push(hand);
                 }
                 future::ready(Ok((judge_card, hands)))
@@ -229,10 +255,28 @@ where
     let Some(judge_card) = judge_card else {
         return Ok(Err(PlayError::NoBlackCard));
     };
-    if hands.len() < players.len() || hands.values().map(Vec::len).min() < Some(chat.pick as usize)
+    // wagered submissions still incomplete are skipped
+    hands.retain(|(_, wager), hand| !wager || hand.len() >= chat.pick as usize);
+    if hands.keys().filter(|(_, wager)| !wager).count() < players.len()
+        || hands.values().map(Vec::len).min() < Some(chat.pick as usize)
     {
+        // before anyone plays, the judge can veto the black card once
+        if chat.redrawn_turn != chat.turn && hands.keys().all(|(player_id, _)| *player_id == 0) {
+            let redraw = InlineQueryResult::Article(InlineQueryResultArticle::new(
+                format!("redraw {}", chat.id),
+                InputMessageContentText::new("I've vetoed the black card"),
+                "🔄 Redraw the black card",
+            ));
+            client
+                .execute(AnswerInlineQuery::new(query_id, [redraw]).with_cache_time(0))
+                .await?;
+            return Ok(Ok(()));
+        }
+
         return Ok(Err(PlayError::NotJudgeTurn));
     }
+    // survival of the fittest, only the submissions still standing
+    hands.retain(|_, hand| !hand.iter().any(|hand| hand.eliminated));
 
     let stream = card::Entity::find()
         .filter(
@@ -247,48 +291,121 @@ where
         .await?;
     let cards = stream
         .try_fold(HashMap::with_capacity(hands.len()), |mut cards, card| {
-            cards.insert(card.id, card.text());
+            cards.insert(card.id, card);
             future::ready(Ok(cards))
         })
         .await?;
+    let black_card = &cards[&judge_card];
 
-    let inline = hands
+    // player id, hand ids and cards of every submission
+    let submissions = hands
         .into_iter()
-        .flat_map(|(player_id, hand)| {
-            let player = &players[&player_id];
-            let len = hand.len();
-            let (hand_ids, hand_texts) = hand.into_iter().fold(
-                (Vec::with_capacity(len), Vec::with_capacity(len)),
-                |(mut ids, mut texts), hand| {
-                    ids.push(hand.id.to_string());
-                    texts.push(cards[&hand.card_id].as_str());
-                    (ids, texts)
-                },
-            );
+        .map(|(key, hand)| {
+            let ids = hand
+                .iter()
+                .map(|hand| hand.id.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let picked = hand
+                .iter()
+                .map(|hand| &cards[&hand.card_id])
+                .collect::<Vec<_>>();
+            (key, ids, picked)
+        })
+        .collect::<Vec<_>>();
+
+    let mut inline = submissions
+        .iter()
+        .flat_map(|((player_id, _), hand_ids, hand_cards)| {
+            let (player, _) = &players[player_id];
+            let len = hand_cards.len();
 
-            let lines = split_multiline_cards(hand_texts.join(" - "), hand_ids.join(" "));
+            let lines = split_multiline_cards(black_card.fill_plain(hand_cards), hand_ids.clone());
 
-            let black_card = &cards[&judge_card];
-            let text = hand_texts.join("\n");
+            let text = black_card.fill(hand_cards);
             lines.into_iter().map(move |(id, line)| {
                 InlineQueryResult::Article(InlineQueryResultArticle::new(
                     id,
                     InputMessageContentText::new(format!(
-                        "*{}*\n\nI've choosen {}'s card{}:\n\n*{}*",
-                        black_card,
+                        "{}\n\nI've {} {}'s card{}",
+                        text,
+                        if chat.survival {
+                            "eliminated"
+                        } else {
+                            "choosen"
+                        },
                         player,
                         if len > 1 { "s" } else { "" },
-                        text
                     ))
                     .with_parse_mode(ParseMode::MarkdownV2),
-                    crate::utils::unescape_markdown(line),
+                    line,
                 ))
             })
         })
         .collect::<Vec<_>>();
 
+    if !chat.survival {
+        inline.push(InlineQueryResult::Article(InlineQueryResultArticle::new(
+            format!("void {}", chat.id),
+            InputMessageContentText::new("I've declared this turn void, nobody wins"),
+            "🚫 No winner",
+        )));
+
+        // a player can't tie with their own wagered submission
+        let ties = submissions
+            .iter()
+            .enumerate()
+            .flat_map(|(index, first)| {
+                let ((first_id, _), _, _) = first;
+                submissions[index + 1..]
+                    .iter()
+                    .filter(move |((second_id, _), _, _)| second_id != first_id)
+                    .map(move |second| (first, second))
+            })
+            .map(
+                |((first_key, _, first_cards), (second_key, _, second_cards))| {
+                    let (first_player, first_name) = &players[&first_key.0];
+                    let (second_player, second_name) = &players[&second_key.0];
+                    InlineQueryResult::Article(
+                        InlineQueryResultArticle::new(
+                            // hand ids could exceed the 64 bytes limit, submissions are
+                            // identified by player id instead
+                            format!(
+                                "tie {} {},{}",
+                                chat.id,
+                                submission_id(*first_key),
+                                submission_id(*second_key)
+                            ),
+                            InputMessageContentText::new(format!(
+                                "{}\n\n{}\n\nIt's a tie between {first_player} and {second_player}",
+                                black_card.fill(first_cards),
+                                black_card.fill(second_cards),
+                            ))
+                            .with_parse_mode(ParseMode::MarkdownV2),
+                            format!("🤝 Tie: {first_name} and {second_name}"),
+                        )
+                        .with_description(format!(
+                            "{} / {}",
+                            black_card.fill_plain(first_cards),
+                            black_card.fill_plain(second_cards)
+                        )),
+                    )
+                },
+            );
+        inline.extend(ties);
+    }
+
+    // Telegram accepts at most 50 results per page, the next ones are asked with an offset
+    let skip = offset.parse::<usize>().unwrap_or_default();
+    let total = inline.len();
+    let page = inline.into_iter().skip(skip).take(50).collect::<Vec<_>>();
+    let answer = AnswerInlineQuery::new(query_id, page).with_cache_time(0);
     client
-        .execute(AnswerInlineQuery::new(query_id, inline).with_cache_time(0))
+        .execute(if total > skip + 50 {
+            answer.with_next_offset((skip + 50).to_string())
+        } else {
+            answer
+        })
         .await?;
 
     Ok(Ok(()))
@@ -306,70 +423,123 @@ where
 {
     let stream = hand::Entity::find()
         .filter(
-            hand::Column::PlayerId.eq(player.id).and(
-                hand::Column::PlayedOnTurn
-                    .eq(chat.turn)
-                    .or(hand::Column::PlayedOnTurn.is_null()),
-            ),
+            hand::Column::PlayerId
+                .eq(player.id)
+                .and(hand::Column::Discarded.eq(false))
+                .and(
+                    hand::Column::PlayedOnTurn
+                        .eq(chat.turn)
+                        .or(hand::Column::PlayedOnTurn.is_null()),
+                ),
         )
         .stream(conn)
         .await?;
 
-    let (played, hands) = stream
-        .try_fold((0, HashMap::new()), |(played, mut hands), hand| {
-            future::ready(Ok((played + hand.played_on_turn.is_some() as i32, {
-                if hand.played_on_turn.is_none() {
+    let (played_hands, hands) = stream
+        .try_fold(
+            (Vec::new(), HashMap::new()),
+            |(mut played_hands, mut hands), hand| {
+                if hand.played_on_turn.is_some() {
+                    played_hands.push(hand);
+                } else {
                     hands.insert(hand.card_id, hand.id);
                 }
-                hands
-            })))
-        })
+                future::ready(Ok((played_hands, hands)))
+            },
+        )
         .await?;
-    if played >= chat.pick {
-        return Ok(Err(PlayError::AlreadyPlayed));
+    let played = played_hands.len() as i32;
+
+    // played cards can be taken back until everyone has played
+    let retract = if played > 0 && !chat.all_played(conn).await? {
+        let mut cards = card::Entity::find()
+            .filter(card::Column::Id.is_in(played_hands.iter().map(|hand| hand.card_id)))
+            .all(conn)
+            .await?
+            .into_iter()
+            .map(|card| (card.id, card))
+            .collect::<HashMap<_, _>>();
+        let mut played_cards = played_hands
+            .into_iter()
+            .filter_map(|hand| cards.remove(&hand.card_id).map(|card| (hand, card)))
+            .collect::<Vec<_>>();
+        played_cards.sort_by_key(|(hand, _)| (hand.wager, hand.seq));
+        super::retract::results(chat, &played_cards)
+    } else {
+        Vec::new()
+    };
+
+    let wager = played >= chat.pick;
+    if wager && !player.can_wager(chat, played as i64) {
+        if retract.is_empty() {
+            return Ok(Err(PlayError::AlreadyPlayed));
+        }
+
+        client
+            .execute(AnswerInlineQuery::new(query_id, retract).with_cache_time(0))
+            .await?;
+        return Ok(Ok(()));
     }
 
-    let stream = card::Entity::find()
+    let white_cards = card::Entity::find()
         .filter(
             card::Column::Id
                 .is_in(hands.keys().copied())
                 .and(card::Column::Color.eq(card::Color::White)),
         )
-        .stream(conn)
+        .all(conn)
         .await?;
-    let cards = stream
+    // complete answers are offered only when starting a submission
+    let answers = if chat.pick > 1 && played % chat.pick == 0 {
+        answers(conn, chat, wager, &hands, &white_cards).await?
+    } else {
+        Vec::new()
+    };
+    let mut cards = stream::iter(white_cards.iter().map(Ok::<_, DbErr>))
         .map_ok(|card| {
             let lines = split_multiline_cards(card.text(), hands[&card.id].to_string());
 
             stream::iter(lines.into_iter().map(|(id, text)| {
                 let res = InlineQueryResultArticle::new(
                     id,
-                    if chat.pick == 1 {
-                        InputMessageContentText::new("I've choosen my card")
-                    } else {
-                        InputMessageContentText::new(format!(
+                    match (wager, chat.pick) {
+                        (false, 1) => InputMessageContentText::new("I've choosen my card"),
+                        (false, _) => InputMessageContentText::new(format!(
                             "I've choosen my {}° card",
                             played + 1
-                        ))
+                        )),
+                        (true, 1) => {
+                            InputMessageContentText::new("I've wagered a point on another card")
+                        }
+                        (true, _) => InputMessageContentText::new(format!(
+                            "I've wagered a point on my {}° extra card",
+                            played - chat.pick + 1
+                        )),
                     },
                     text,
                 );
-                Ok::<_, DbErr>(InlineQueryResult::Article(if played + 1 < chat.pick {
-                    res.with_reply_markup([[
-                        InlineKeyboardButton::for_switch_inline_query_current_chat(
-                            "Open cards hand",
-                            chat.id.to_string(),
-                        ),
-                    ]])
-                } else {
-                    res
-                }))
+                Ok::<_, DbErr>(InlineQueryResult::Article(
+                    if (played + 1) % chat.pick != 0 {
+                        res.with_reply_markup([[
+                            InlineKeyboardButton::for_switch_inline_query_current_chat(
+                                "Open cards hand",
+                                chat.id.to_string(),
+                            ),
+                        ]])
+                    } else {
+                        res
+                    },
+                ))
             }))
         })
         .try_flatten()
         .try_collect::<Vec<_>>()
         .await?;
 
+    // Telegram accepts at most 50 results
+    let room = 50_usize.saturating_sub(cards.len() + retract.len());
+    cards.extend(answers.into_iter().take(room));
+    cards.extend(retract);
     client
         .execute(AnswerInlineQuery::new(query_id, cards).with_cache_time(0))
         .await?;
@@ -377,6 +547,125 @@ where
     Ok(Ok(()))
 }
 
+/// ordered combinations filling the whole black card, to be played at once
+async fn answers<C>(
+    conn: &C,
+    chat: &chat::Model,
+    wager: bool,
+    hands: &HashMap<i32, i32>,
+    white_cards: &[card::Model],
+) -> Result<Vec<InlineQueryResult>, Error>
+where
+    C: ConnectionTrait,
+{
+    // the only black card played this turn is the judge's one
+    let played = hand::Entity::find()
+        .filter(
+            hand::Column::ChatId
+                .eq(chat.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn)),
+        )
+        .all(conn)
+        .await?;
+    let Some(black_card) = card::Entity::find()
+        .filter(
+            card::Column::Id
+                .is_in(played.iter().map(|hand| hand.card_id))
+                .and(card::Column::Color.eq(card::Color::Black)),
+        )
+        .one(conn)
+        .await?
+    else {
+        return Ok(Vec::new());
+    };
+
+    // Telegram accepts at most 50 results
+    Ok(permutations(white_cards.len(), chat.pick as usize, 50)
+        .into_iter()
+        .map(|combination| {
+            let answer = combination
+                .iter()
+                .map(|index| &white_cards[*index])
+                .collect::<Vec<_>>();
+            let hand_ids = answer
+                .iter()
+                .map(|card| hands[&card.id].to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            InlineQueryResult::Article(
+                InlineQueryResultArticle::new(
+                    hand_ids,
+                    InputMessageContentText::new(if wager {
+                        "I've wagered a point on another answer"
+                    } else {
+                        "I've choosen my cards"
+                    }),
+                    answer
+                        .iter()
+                        .map(|card| card.plain_text().trim())
+                        .collect::<Vec<_>>()
+                        .join(" ➕ "),
+                )
+                .with_description(black_card.fill_plain(&answer)),
+            )
+        })
+        .collect())
+}
+
+/// ordered selections of `pick` distinct indexes out of `len`, up to `limit`,
+/// best effort, alternating the first index to give every card a chance
+fn permutations(len: usize, pick: usize, limit: usize) -> Vec<Vec<usize>> {
+    if pick == 0 || pick > len {
+        return Vec::new();
+    }
+
+    let mut tails = (0..len)
+        .map(|first| selections(len, pick - 1, vec![first]))
+        .collect::<Vec<_>>();
+    let mut combinations = Vec::with_capacity(limit);
+    while combinations.len() < limit {
+        let found = combinations.len();
+        for tail in &mut tails {
+            if combinations.len() == limit {
+                break;
+            }
+            combinations.extend(tail.next());
+        }
+        if combinations.len() == found {
+            break;
+        }
+    }
+    combinations
+}
+
+/// lazily extends `head` with `left` more distinct indexes out of `len`
+fn selections(len: usize, left: usize, head: Vec<usize>) -> Box<dyn Iterator<Item = Vec<usize>>> {
+    if left == 0 {
+        return Box::new(iter::once(head));
+    }
+
+    let taken = head.clone();
+    Box::new(
+        (0..len)
+            .filter(move |index| !taken.contains(index))
+            .map(move |index| {
+                let mut next = head.clone();
+                next.push(index);
+                next
+            })
+            .flat_map(move |next| selections(len, left - 1, next)),
+    )
+}
+
+/// player id of a submission, with a trailing 'w' when wagered
+fn submission_id((player_id, wager): (i32, bool)) -> String {
+    if wager {
+        format!("{player_id}w")
+    } else {
+        player_id.to_string()
+    }
+}
+
 /// split text in multiple lines if needed
 /// official line limit is 127 chars
 /// but text is trucated based on screen width
@@ -384,7 +673,7 @@ where
 ///
 /// Telegram doesn't accept multiple inputs with the same id
 /// so we are appending a ";{index}"
-fn split_multiline_cards(text: String, id: String) -> Vec<(String, String)> {
+pub(super) fn split_multiline_cards(text: String, id: String) -> Vec<(String, String)> {
     let text_len = text.chars().count();
     if text_len > 50 {
         text.split_whitespace()
@@ -408,3 +697,64 @@ fn split_multiline_cards(text: String, id: String) -> Vec<(String, String)> {
         vec![(id, text)]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::permutations;
+
+    #[test]
+    fn pick_one() {
+        assert_eq!(permutations(3, 1, 50), vec![vec![0], vec![1], vec![2]]);
+    }
+
+    #[test]
+    fn pick_two() {
+        let combinations = permutations(3, 2, 50);
+        assert_eq!(
+            combinations,
+            vec![
+                vec![0, 1],
+                vec![1, 0],
+                vec![2, 0],
+                vec![0, 2],
+                vec![1, 2],
+                vec![2, 1],
+            ]
+        );
+    }
+
+    #[test]
+    fn pick_three() {
+        let combinations = permutations(10, 3, 50);
+        assert_eq!(combinations.len(), 50);
+        // every card gets a chance to be the first one
+        assert_eq!(
+            combinations[..10]
+                .iter()
+                .map(|combination| combination[0])
+                .collect::<Vec<_>>(),
+            (0..10).collect::<Vec<_>>()
+        );
+        // no duplicated combinations, no repeated cards
+        assert_eq!(combinations.iter().collect::<HashSet<_>>().len(), 50);
+        assert!(combinations.iter().all(|combination| combination
+            .iter()
+            .collect::<HashSet<_>>()
+            .len()
+            == 3));
+    }
+
+    #[test]
+    fn every_combination_below_limit() {
+        assert_eq!(permutations(4, 3, 50).len(), 24);
+    }
+
+    #[test]
+    fn hand_smaller_than_pick() {
+        assert!(permutations(1, 2, 50).is_empty());
+        assert!(permutations(2, 3, 50).is_empty());
+        assert!(permutations(0, 1, 50).is_empty());
+    }
+}
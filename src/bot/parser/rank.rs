@@ -1,14 +1,11 @@
-use futures_util::TryStreamExt;
-use sea_orm::{
-    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, StreamTrait,
-};
+use sea_orm::{ConnectionTrait, StreamTrait};
 use tgbot::{
     api::Client,
     types::{InlineKeyboardButton, ParseMode, ReplyParameters, SendMessage},
 };
 
 use crate::{
-    entities::{chat, hand, player},
+    entities::{chat, rating},
     Error,
 };
 
@@ -21,44 +18,19 @@ pub async fn execute<C>(
 where
     C: ConnectionTrait + StreamTrait,
 {
-    let stream = player::Entity::find()
-        .filter(player::Column::ChatId.eq(chat.id))
-        .order_by_desc(player::Column::Points)
-        .stream(conn)
-        .await?;
-
-    let mut players = stream
-        .map_ok(|player| {
-            (
-                player.points as i64,
-                format!("\n{} {} points", player.tg_link(), player.points),
-            )
-        })
-        .try_collect::<Vec<_>>()
-        .await?;
-
-    if chat.rando_carlissian {
-        let won = hand::Entity::find()
-            .filter(
-                hand::Column::ChatId
-                    .eq(chat.id)
-                    .and(hand::Column::PlayerId.eq(0))
-                    .and(hand::Column::Won.eq(true)),
-            )
-            .select_only()
-            .column_as(hand::Column::Id.count(), "ids")
-            .into_tuple::<Option<i64>>()
-            .one(conn)
-            .await?
-            .flatten()
-            .unwrap_or_default();
-        players.push((won, format!("\n{} {won} points", crate::RANDO_CARLISSIAN)));
-        players.sort_by(|(points_a, _), (points_b, _)| points_b.cmp(points_a));
-    }
+    let players = chat.scoreboard(conn).await?;
+    let ratings =
+        rating::find(conn, players.iter().map(|(_, telegram_id, _)| *telegram_id)).await?;
 
     let mut msg = format!("Turn {}\n", chat.turn);
-    for (_, player) in players {
-        msg.push_str(&player);
+    for (points, telegram_id, player) in players {
+        msg.push_str(&format!(
+            "\n{player} {points} points, rating {}",
+            ratings
+                .get(&telegram_id)
+                .copied()
+                .unwrap_or(rating::INITIAL_RATING)
+        ));
     }
 
     client
@@ -0,0 +1,53 @@
+use sea_orm::{ConnectionTrait, EntityTrait, QueryOrder, QuerySelect};
+use tgbot::{
+    api::Client,
+    types::{ParseMode, ReplyParameters, SendMessage},
+};
+
+use crate::{
+    entities::{chat, player, rating},
+    Error,
+};
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    message_id: i64,
+    chat: &chat::Model,
+) -> Result<(), Error>
+where
+    C: ConnectionTrait,
+{
+    let ratings = rating::Entity::find()
+        .order_by_desc(rating::Column::Rating)
+        .limit(20)
+        .all(conn)
+        .await?;
+
+    let names = player::tg_links(conn, ratings.iter().map(|rating| rating.telegram_id)).await?;
+
+    let mut msg = String::from("Global ratings\n");
+    for (position, rating) in ratings.into_iter().enumerate() {
+        let name = names
+            .get(&rating.telegram_id)
+            .map(String::as_str)
+            .unwrap_or(crate::RANDO_CARLISSIAN);
+        msg.push_str(&format!(
+            "\n{}\\. {name} {} after {} game{}",
+            position + 1,
+            rating.rating,
+            rating.games,
+            if rating.games == 1 { "" } else { "s" },
+        ));
+    }
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect,
+    TransactionTrait,
+};
+use tgbot::{
+    api::Client,
+    types::{InlineKeyboardButton, ParseMode, ReplyParameters, SendMessage, User},
+};
+
+use crate::{
+    entities::{chat, hand, player},
+    Error,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum RebootError {
+    #[error(
+        "Rebooting the Universe isn't allowed in this game, the owner can enable it from /settings"
+    )]
+    Disabled,
+    #[error("The game hasn't begun yet, the owner can /begin it")]
+    NotStarted,
+    #[error("You aren't playing in this game")]
+    PlayerNotFound,
+    #[error("You need at least a point to reboot the Universe")]
+    NoPoints,
+    #[error("You've already played this turn, wait for the next one")]
+    AlreadyPlayed,
+    #[error(transparent)]
+    Hand(#[from] hand::PickError),
+}
+
+/// "Rebooting the Universe" house rule: trade a point for a brand new hand
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    message_id: i64,
+    chat: &chat::Model,
+) -> Result<Result<(), RebootError>, Error>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if !chat.reboot {
+        return Ok(Err(RebootError::Disabled));
+    }
+
+    if !chat.started || chat.end_date.is_some() {
+        return Ok(Err(RebootError::NotStarted));
+    }
+
+    let Some(player) = player::Entity::find()
+        .filter(
+            player::Column::TelegramId
+                .eq(i64::from(user.id))
+                .and(player::Column::ChatId.eq(chat.id)),
+        )
+        .one(conn)
+        .await?
+    else {
+        return Ok(Err(RebootError::PlayerNotFound));
+    };
+
+    if player.points < 1 {
+        return Ok(Err(RebootError::NoPoints));
+    }
+
+    let played = hand::Entity::find()
+        .filter(
+            hand::Column::PlayerId
+                .eq(player.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn)),
+        )
+        .select_only()
+        .column_as(hand::Column::Id.count(), "ids")
+        .into_tuple::<Option<i64>>()
+        .one(conn)
+        .await?
+        .flatten()
+        .unwrap_or_default();
+    // the judge holds the black card
+    if played > player.is_my_turn(chat) as i64 {
+        return Ok(Err(RebootError::AlreadyPlayed));
+    }
+
+    let txn = conn.begin().await?;
+
+    // the point is spent first, concurrent reboots can't both pay with the same point
+    let paid = player::Entity::update_many()
+        .col_expr(
+            player::Column::Points,
+            Expr::col(player::Column::Points).sub(1),
+        )
+        .filter(
+            player::Column::Id
+                .eq(player.id)
+                .and(player::Column::Points.gte(1)),
+        )
+        .exec(&txn)
+        .await?;
+    if paid.rows_affected == 0 {
+        return Ok(Err(RebootError::NoPoints));
+    }
+
+    hand::Entity::update_many()
+        .col_expr(hand::Column::Discarded, Expr::value(true))
+        .filter(
+            hand::Column::PlayerId
+                .eq(player.id)
+                .and(hand::Column::PlayedOnTurn.is_null())
+                .and(hand::Column::Discarded.eq(false)),
+        )
+        .exec(&txn)
+        .await?;
+
+    if let Err(e) = hand::pick(&txn, player.id, chat.id, chat.turn, false).await? {
+        return Ok(Err(RebootError::from(e)));
+    }
+
+    txn.commit().await?;
+
+    client
+        .execute(
+            SendMessage::new(
+                chat.telegram_id,
+                format!(
+                    "{} rebooted the Universe, trading a point for a new hand",
+                    player.tg_link()
+                ),
+            )
+            .with_reply_parameters(ReplyParameters::new(message_id))
+            .with_reply_markup(
+                [[InlineKeyboardButton::for_switch_inline_query_current_chat(
+                    "Open cards hand",
+                    chat.id.to_string(),
+                )]],
+            )
+            .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(Ok(()))
+}
@@ -0,0 +1,168 @@
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
+    QueryFilter, QuerySelect, TransactionTrait,
+};
+use tgbot::{
+    api::Client,
+    types::{InlineKeyboardButton, ParseMode, ReplyParameters, SendMessage, User},
+};
+
+use crate::{
+    entities::{chat, hand, player},
+    Error,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum RedrawError {
+    #[error("The game hasn't begun yet, the owner can /begin it")]
+    NotStarted,
+    #[error("Only the judge can redraw the black card")]
+    NotJudge,
+    #[error("The black card has already been redrawn this turn")]
+    AlreadyRedrawn,
+    #[error("Someone already played on this black card")]
+    AlreadyPlayed,
+    #[error("No black card picked \\(this is a bug\\)")]
+    NoBlackCard,
+    #[error(transparent)]
+    Hand(#[from] hand::PickError),
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    message_id: Option<i64>,
+    chat: &chat::Model,
+) -> Result<Result<(), RedrawError>, Error>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if !chat.started || chat.end_date.is_some() {
+        return Ok(Err(RedrawError::NotStarted));
+    }
+
+    let Some(judge) = player::Entity::find()
+        .filter(
+            player::Column::TelegramId
+                .eq(i64::from(user.id))
+                .and(player::Column::ChatId.eq(chat.id)),
+        )
+        .one(conn)
+        .await?
+    else {
+        return Ok(Ok(()));
+    };
+
+    if !judge.is_my_turn(chat) {
+        return Ok(Err(RedrawError::NotJudge));
+    }
+
+    if chat.redrawn_turn == chat.turn {
+        return Ok(Err(RedrawError::AlreadyRedrawn));
+    }
+
+    if played(conn, &judge, chat).await? > 0 {
+        return Ok(Err(RedrawError::AlreadyPlayed));
+    }
+
+    let txn = conn.begin().await?;
+
+    // the old black card is thrown away, it won't come back
+    hand::Entity::update_many()
+        .col_expr(hand::Column::Discarded, Expr::value(true))
+        .col_expr(hand::Column::PlayedOnTurn, Expr::value(Option::<i32>::None))
+        .filter(
+            hand::Column::ChatId
+                .eq(chat.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn))
+                .and(hand::Column::PlayerId.eq(judge.id)),
+        )
+        .exec(&txn)
+        .await?;
+
+    // rando carlissian plays as many cards as the black card asks
+    hand::Entity::delete_many()
+        .filter(
+            hand::Column::ChatId
+                .eq(chat.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn))
+                .and(hand::Column::PlayerId.eq(0)),
+        )
+        .exec(&txn)
+        .await?;
+
+    let card = match hand::pick(&txn, judge.id, chat.id, chat.turn, true).await? {
+        Ok(Some(card)) => card,
+        Ok(None) => return Ok(Err(RedrawError::NoBlackCard)),
+        Err(e) => return Ok(Err(RedrawError::from(e))),
+    };
+
+    let pick = card.pick();
+    if chat.rando_carlissian {
+        for _ in 0..pick {
+            if let Err(e) = hand::pick(&txn, 0, chat.id, chat.turn, false).await? {
+                return Ok(Err(RedrawError::from(e)));
+            }
+        }
+    }
+
+    chat::ActiveModel {
+        id: ActiveValue::Set(chat.id),
+        pick: ActiveValue::Set(pick),
+        redrawn_turn: ActiveValue::Set(chat.turn),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    let msg = SendMessage::new(
+        chat.telegram_id,
+        format!(
+            "{} redrew the black card\n\nTurn {}\n\n{}",
+            judge.tg_link(),
+            chat.turn,
+            card.descr()
+        ),
+    )
+    .with_reply_markup(
+        [[InlineKeyboardButton::for_switch_inline_query_current_chat(
+            "Open cards hand",
+            chat.id.to_string(),
+        )]],
+    )
+    .with_parse_mode(ParseMode::MarkdownV2);
+    client
+        .execute(if let Some(message_id) = message_id {
+            msg.with_reply_parameters(ReplyParameters::new(message_id))
+        } else {
+            msg
+        })
+        .await?;
+
+    Ok(Ok(()))
+}
+
+/// white cards played this turn, Rando Carlissian's excluded
+async fn played<C>(conn: &C, judge: &player::Model, chat: &chat::Model) -> Result<i64, Error>
+where
+    C: ConnectionTrait,
+{
+    Ok(hand::Entity::find()
+        .filter(
+            hand::Column::ChatId
+                .eq(chat.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn))
+                .and(hand::Column::PlayerId.gt(0))
+                .and(hand::Column::PlayerId.ne(judge.id)),
+        )
+        .select_only()
+        .column_as(hand::Column::Id.count(), "count")
+        .into_tuple::<Option<i64>>()
+        .one(conn)
+        .await?
+        .flatten()
+        .unwrap_or_default())
+}
@@ -0,0 +1,123 @@
+use rand::seq::SliceRandom;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter,
+    QueryOrder, TransactionTrait,
+};
+use tgbot::{
+    api::Client,
+    types::{InlineKeyboardButton, ParseMode, ReplyParameters, SendMessage, User},
+};
+
+use crate::{
+    entities::{chat, player},
+    Error,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum RematchError {
+    #[error("There is already a game in this chat, /close it first")]
+    AlreadyRunning,
+    #[error("There is no previous game in this chat")]
+    NoPreviousGame,
+    #[error("You weren't part of the previous game")]
+    NotPlayer,
+    #[error(transparent)]
+    Begin(#[from] super::begin::BeginError),
+    #[error(transparent)]
+    Chat(#[from] chat::ChatError),
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    message_id: i64,
+    chat: &chat::Model,
+) -> Result<Result<(), RematchError>, Error>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if chat.started || chat.players > 0 {
+        return Ok(Err(RematchError::AlreadyRunning));
+    }
+
+    let Some(previous) = chat::Entity::find()
+        .filter(
+            chat::Column::TelegramId
+                .eq(chat.telegram_id)
+                .and(chat::Column::EndDate.is_not_null()),
+        )
+        .order_by_desc(chat::Column::EndDate)
+        .one(conn)
+        .await?
+    else {
+        return Ok(Err(RematchError::NoPreviousGame));
+    };
+
+    let mut players = player::Entity::find()
+        .filter(player::Column::ChatId.eq(previous.id))
+        .all(conn)
+        .await?;
+    if !players
+        .iter()
+        .any(|player| player.telegram_id == i64::from(user.id))
+    {
+        return Ok(Err(RematchError::NotPlayer));
+    }
+    let owner = players
+        .iter()
+        .find(|player| Some(player.id) == previous.owner)
+        .map(|player| player.telegram_id);
+
+    // new game, new turns order
+    players.shuffle(&mut rand::thread_rng());
+
+    let txn = conn.begin().await?;
+
+    previous.copy_settings(&txn, chat.id).await?;
+
+    let mut owner_id = None;
+    for player in &players {
+        let new_player = player.copy(&txn, chat.id).await?;
+        if owner == Some(player.telegram_id) || owner_id.is_none() {
+            owner_id = Some(new_player.id);
+        }
+    }
+
+    let chat = chat::ActiveModel {
+        id: ActiveValue::Set(chat.id),
+        owner: ActiveValue::Set(owner_id),
+        players: ActiveValue::Set(players.len() as i32),
+        started: ActiveValue::Set(true),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await?;
+
+    // same checks as /begin, with the previous game players and settings
+    if let Err(e) = super::begin::validate(&txn, &chat).await? {
+        return Ok(Err(RematchError::from(e)));
+    }
+
+    let msg = match chat.reset(&txn).await? {
+        Ok(msg) => msg,
+        Err(e) => return Ok(Err(RematchError::from(e))),
+    };
+    txn.commit().await?;
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, format!("Rematch\\!\n\n{msg}"))
+                .with_reply_markup(
+                    [[InlineKeyboardButton::for_switch_inline_query_current_chat(
+                        "Open cards hand",
+                        chat.id.to_string(),
+                    )]],
+                )
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(Ok(()))
+}
@@ -0,0 +1,178 @@
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, EntityTrait,
+    QueryFilter, QueryOrder, TransactionTrait,
+};
+use tgbot::types::{
+    InlineKeyboardButton, InlineQueryResult, InlineQueryResultArticle, InputMessageContentText,
+    User,
+};
+
+use crate::{
+    entities::{card, chat, hand, player},
+    Error,
+};
+
+/// inline results to withdraw or reorder the cards played this turn, ordered by seq
+pub fn results(
+    chat: &chat::Model,
+    played: &[(hand::Model, card::Model)],
+) -> Vec<InlineQueryResult> {
+    let open = || {
+        [[InlineKeyboardButton::for_switch_inline_query_current_chat(
+            "Open cards hand",
+            chat.id.to_string(),
+        )]]
+    };
+
+    let mut results = vec![InlineQueryResult::Article(
+        InlineQueryResultArticle::new(
+            format!("withdraw {}", chat.id),
+            InputMessageContentText::new("I've withdrawn my submission"),
+            "↩️ Withdraw my submission",
+        )
+        .with_reply_markup(open()),
+    )];
+    if chat.pick > 1 {
+        results.extend(
+            played
+                .iter()
+                .filter(|(hand, _)| hand.seq > 0)
+                .map(|(hand, card)| {
+                    InlineQueryResult::Article(
+                        InlineQueryResultArticle::new(
+                            format!("first {}", hand.id),
+                            InputMessageContentText::new("I've changed my cards order"),
+                            format!("⬆️ Play first: {}", card.plain_text()),
+                        )
+                        .with_reply_markup(open()),
+                    )
+                }),
+        );
+    }
+    results
+}
+
+async fn find_player<C>(
+    conn: &C,
+    user: &User,
+    chat: &chat::Model,
+) -> Result<Option<player::Model>, Error>
+where
+    C: ConnectionTrait,
+{
+    Ok(player::Entity::find()
+        .filter(
+            player::Column::TelegramId
+                .eq(i64::from(user.id))
+                .and(player::Column::ChatId.eq(chat.id)),
+        )
+        .one(conn)
+        .await?)
+}
+
+/// takes back every card played this turn, the wager included
+pub async fn withdraw<C>(conn: &C, user: &User, chat_id: i32) -> Result<(), Error>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let Some(chat) = chat::Entity::find_by_id(chat_id).one(conn).await? else {
+        return Ok(());
+    };
+    // too late, the judge is already choosing
+    if chat.end_date.is_some() || chat.all_played(conn).await? {
+        return Ok(());
+    }
+
+    let Some(player) = find_player(conn, user, &chat).await? else {
+        return Ok(());
+    };
+    if player.is_my_turn(&chat) {
+        return Ok(());
+    }
+
+    let txn = conn.begin().await?;
+    let hands = hand::Entity::find()
+        .filter(
+            hand::Column::PlayerId
+                .eq(player.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn)),
+        )
+        .all(&txn)
+        .await?;
+    for hand in &hands {
+        hand::ActiveModel {
+            id: ActiveValue::Set(hand.id),
+            played_on_turn: ActiveValue::Set(None),
+            seq: ActiveValue::Set(0),
+            wager: ActiveValue::Set(false),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
+    }
+    if hands.iter().any(|hand| hand.wager) {
+        player::Entity::update_many()
+            .col_expr(
+                player::Column::Points,
+                Expr::col(player::Column::Points).add(1),
+            )
+            .filter(player::Column::Id.eq(player.id))
+            .exec(&txn)
+            .await?;
+    }
+    txn.commit().await?;
+
+    Ok(())
+}
+
+/// moves a played card to the first blank, the others keep their order
+pub async fn reorder<C>(conn: &C, user: &User, hand_id: i32) -> Result<(), Error>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let Some(first) = hand::Entity::find_by_id(hand_id).one(conn).await? else {
+        return Ok(());
+    };
+    let Some(chat) = chat::Entity::find_by_id(first.chat_id).one(conn).await? else {
+        return Ok(());
+    };
+    if chat.end_date.is_some()
+        || first.played_on_turn != Some(chat.turn)
+        || chat.all_played(conn).await?
+    {
+        return Ok(());
+    }
+
+    let Some(player) = find_player(conn, user, &chat).await? else {
+        return Ok(());
+    };
+    if first.player_id != player.id {
+        return Ok(());
+    }
+
+    let hands = hand::Entity::find()
+        .filter(
+            hand::Column::PlayerId
+                .eq(player.id)
+                .and(hand::Column::PlayedOnTurn.eq(chat.turn))
+                .and(hand::Column::Wager.eq(first.wager))
+                .and(hand::Column::Id.ne(first.id)),
+        )
+        .order_by_asc(hand::Column::Seq)
+        .all(conn)
+        .await?;
+
+    let txn = conn.begin().await?;
+    for (seq, hand) in [first].iter().chain(&hands).enumerate() {
+        hand::ActiveModel {
+            id: ActiveValue::Set(hand.id),
+            seq: ActiveValue::Set(seq as i32),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
+    }
+    txn.commit().await?;
+
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QueryOrder, StreamTrait,
+    TransactionTrait,
+};
+use tgbot::{
+    api::Client,
+    types::{ParseMode, ReplyParameters, SendMessage},
+};
+use tracing::warn;
+
+use crate::{
+    entities::{chat, player, season, season_standing},
+    Error,
+};
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    message_id: i64,
+    chat: &chat::Model,
+    period: season::Period,
+) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let (start, end) = period.current();
+    let chat_standings = season::standings(conn, start, end, Some(chat.telegram_id)).await?;
+    let global_standings = season::standings(conn, start, end, None).await?;
+    let names = player::tg_links(
+        conn,
+        chat_standings
+            .iter()
+            .chain(global_standings.iter().take(5))
+            .map(|standing| standing.telegram_id),
+    )
+    .await?;
+
+    let days = (end - Utc::now().date_naive()).num_days();
+    let mut msg = format!(
+        "Season {}, {days} day{} remaining\n\nChat standings:",
+        period.name(start),
+        if days == 1 { "" } else { "s" },
+    );
+    append_standings(
+        &mut msg,
+        chat_standings
+            .iter()
+            .take(10)
+            .map(|standing| (standing.telegram_id, standing.games_won, standing.points)),
+        &names,
+    );
+    msg.push_str("\n\nGlobal standings:");
+    append_standings(
+        &mut msg,
+        global_standings
+            .iter()
+            .take(5)
+            .map(|standing| (standing.telegram_id, standing.games_won, standing.points)),
+        &names,
+    );
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// archives the previous season when a new one begins, and sends its summary to participating chats
+pub async fn rollover<C>(client: &Client, conn: &C, period: season::Period) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait + TransactionTrait,
+{
+    // a failure leaves nothing half archived, it will be retried on next poll
+    let txn = conn.begin().await?;
+    let archived = season::archive(&txn, period).await?;
+    txn.commit().await?;
+
+    for (season, telegram_ids) in archived {
+        announce(client, conn, period, &season, telegram_ids).await?;
+    }
+
+    Ok(())
+}
+
+async fn announce<C>(
+    client: &Client,
+    conn: &C,
+    period: season::Period,
+    season: &season::Model,
+    telegram_ids: Vec<i64>,
+) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let standings = season_standing::Entity::find()
+        .filter(season_standing::Column::SeasonId.eq(season.id))
+        .order_by_asc(season_standing::Column::Position)
+        .all(conn)
+        .await?;
+    let names =
+        player::tg_links(conn, standings.iter().map(|standing| standing.telegram_id)).await?;
+    let by_chat =
+        standings
+            .into_iter()
+            .fold(HashMap::<_, Vec<_>>::new(), |mut by_chat, standing| {
+                by_chat.entry(standing.chat_id).or_default().push((
+                    standing.telegram_id,
+                    standing.games_won as i64,
+                    standing.points as i64,
+                ));
+                by_chat
+            });
+
+    let mut global = String::new();
+    append_standings(
+        &mut global,
+        by_chat.get(&0).into_iter().flatten().take(3).copied(),
+        &names,
+    );
+
+    for telegram_id in telegram_ids {
+        let mut msg = format!(
+            "Season {} is over\\!\n\nFinal standings:",
+            period.name(season.start_date)
+        );
+        append_standings(
+            &mut msg,
+            by_chat
+                .get(&telegram_id)
+                .into_iter()
+                .flatten()
+                .take(10)
+                .copied(),
+            &names,
+        );
+        msg.push_str("\n\nGlobal podium:");
+        msg.push_str(&global);
+        msg.push_str("\n\nA new season has begun, /season and /top standings start over");
+
+        // a chat that kicked the bot shouldn't block the others
+        if let Err(err) = client
+            .execute(SendMessage::new(telegram_id, msg).with_parse_mode(ParseMode::MarkdownV2))
+            .await
+        {
+            warn!("Can't send season summary to {telegram_id}: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+fn append_standings(
+    msg: &mut String,
+    standings: impl Iterator<Item = (i64, i64, i64)>,
+    names: &HashMap<i64, String>,
+) {
+    let mut empty = true;
+    for (position, (telegram_id, games_won, points)) in standings.enumerate() {
+        empty = false;
+        msg.push_str(&format!(
+            "\n{}\\. {} {games_won} game{} won, {points} points",
+            position + 1,
+            names
+                .get(&telegram_id)
+                .map(String::as_str)
+                .unwrap_or(crate::RANDO_CARLISSIAN),
+            if games_won == 1 { "" } else { "s" },
+        ));
+    }
+    if empty {
+        msg.push_str("\nno games closed yet");
+    }
+}
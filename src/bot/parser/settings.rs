@@ -4,18 +4,17 @@ use sea_orm::{
 };
 use tgbot::{
     api::Client,
-    types::{
-        EditMessageReplyMarkup, InlineKeyboardButton, ParseMode, ReplyParameters, SendMessage, User,
-    },
+    types::{EditMessageReplyMarkup, InlineKeyboardButton, ReplyParameters, SendMessage, User},
 };
 
 use crate::{
-    entities::{chat, chat_pack, pack, player},
+    entities::{chat, chat_default, chat_pack, pack, player},
     Error,
 };
 
 const ENABLED: &str = "☑";
 const DISABLED: &str = "◻";
+const MAX_DISCARDS: i32 = 5;
 
 #[derive(thiserror::Error, Debug)]
 pub enum SettingsError {
@@ -50,7 +49,7 @@ where
         return Ok(Ok(()));
     };
 
-    if chat.turn > 1 {
+    if chat.started {
         return Ok(Err(SettingsError::AlreadyStarter));
     }
 
@@ -86,50 +85,149 @@ where
     let mut all_officials_enabled = officials.iter().all(|id| enabled.contains(id));
 
     let mut rando_carlissian = chat.rando_carlissian;
+    let mut global_leaderboard = chat.global_leaderboard;
+    let mut peoples_choice = chat.peoples_choice;
+    let mut god_is_dead = chat.god_is_dead;
+    let mut rotation = chat.rotation;
+    let mut reboot = chat.reboot;
+    let mut discards = chat.discards;
+    let mut gambling = chat.gambling;
+    let mut survival = chat.survival;
+    let mut single_pick = chat.single_pick;
+    let mut reveal_submissions = chat.reveal_submissions;
     let mut close = false;
     let mut start = 0;
     if let Some(data) = query_data {
+        let mut changed = true;
         match data {
             "close" => {
                 close = true;
+                changed = false;
             }
             action if action.starts_with("skip") => {
                 start = action[4..].parse().unwrap_or_default();
+                changed = false;
             }
             action if action.starts_with("rando") => {
                 start = action[5..].parse().unwrap_or_default();
-                let txn = conn.begin().await?;
-
                 let chat = chat::ActiveModel {
                     id: ActiveValue::Set(chat.id),
                     rando_carlissian: ActiveValue::Set(!chat.rando_carlissian),
                     ..Default::default()
                 }
-                .update(&txn)
+                .update(conn)
                 .await?;
                 rando_carlissian = chat.rando_carlissian;
-
-                let msg = match chat.reset(&txn).await? {
-                    Ok(msg) => msg,
-                    Err(e) => return Ok(Err(SettingsError::from(e))),
-                };
-                txn.commit().await?;
-
-                if chat.players + chat.rando_carlissian as i32 > 2 {
-                    client
-                        .execute(
-                            SendMessage::new(chat.telegram_id, msg)
-                                .with_reply_markup([[
-                                    InlineKeyboardButton::for_switch_inline_query_current_chat(
-                                        "Open cards hand",
-                                        chat.id.to_string(),
-                                    ),
-                                ]])
-                                .with_reply_parameters(ReplyParameters::new(message_id))
-                                .with_parse_mode(ParseMode::MarkdownV2),
-                        )
-                        .await?;
+            }
+            action if action.starts_with("global") => {
+                start = action[6..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    global_leaderboard: ActiveValue::Set(!chat.global_leaderboard),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+                global_leaderboard = chat.global_leaderboard;
+            }
+            action if action.starts_with("people") => {
+                start = action[6..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    peoples_choice: ActiveValue::Set(!chat.peoples_choice),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+                peoples_choice = chat.peoples_choice;
+            }
+            action if action.starts_with("god") => {
+                start = action[3..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    god_is_dead: ActiveValue::Set(!chat.god_is_dead),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+                god_is_dead = chat.god_is_dead;
+            }
+            action if action.starts_with("rotation") => {
+                start = action[8..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    rotation: ActiveValue::Set(chat.rotation.next()),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+                rotation = chat.rotation;
+            }
+            action if action.starts_with("reboot") => {
+                start = action[6..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    reboot: ActiveValue::Set(!chat.reboot),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+                reboot = chat.reboot;
+            }
+            action if action.starts_with("discards") => {
+                start = action[8..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    discards: ActiveValue::Set((chat.discards + 1) % (MAX_DISCARDS + 1)),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+                discards = chat.discards;
+            }
+            action if action.starts_with("gambling") => {
+                start = action[8..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    gambling: ActiveValue::Set(!chat.gambling),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+                gambling = chat.gambling;
+            }
+            action if action.starts_with("survival") => {
+                start = action[8..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    survival: ActiveValue::Set(!chat.survival),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+                survival = chat.survival;
+            }
+            action if action.starts_with("single") => {
+                start = action[6..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    single_pick: ActiveValue::Set(!chat.single_pick),
+                    ..Default::default()
                 }
+                .update(conn)
+                .await?;
+                single_pick = chat.single_pick;
+            }
+            action if action.starts_with("reveal") => {
+                start = action[6..].parse().unwrap_or_default();
+                let chat = chat::ActiveModel {
+                    id: ActiveValue::Set(chat.id),
+                    reveal_submissions: ActiveValue::Set(!chat.reveal_submissions),
+                    ..Default::default()
+                }
+                .update(conn)
+                .await?;
+                reveal_submissions = chat.reveal_submissions;
             }
             action if action.starts_with("all") => {
                 start = action[3..].parse().unwrap_or_default();
@@ -217,6 +315,11 @@ where
                 }
             }
         }
+
+        // remember settings for next games in this chat
+        if changed {
+            chat_default::save(conn, chat.id).await?;
+        }
     }
 
     let keyboard = if close {
@@ -231,6 +334,75 @@ where
             ),
             format!("rando{start}"),
         )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!(
+                "Global leaderboard {}",
+                if global_leaderboard {
+                    ENABLED
+                } else {
+                    DISABLED
+                }
+            ),
+            format!("global{start}"),
+        )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!(
+                "People's choice {}",
+                if peoples_choice { ENABLED } else { DISABLED }
+            ),
+            format!("people{start}"),
+        )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!(
+                "God is dead, no judge {}",
+                if god_is_dead { ENABLED } else { DISABLED }
+            ),
+            format!("god{start}"),
+        )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!("Judge rotation: {}", rotation.name()),
+            format!("rotation{start}"),
+        )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!(
+                "Rebooting the Universe {}",
+                if reboot { ENABLED } else { DISABLED }
+            ),
+            format!("reboot{start}"),
+        )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!("Free discards per player: {discards}"),
+            format!("discards{start}"),
+        )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!("Gambling {}", if gambling { ENABLED } else { DISABLED }),
+            format!("gambling{start}"),
+        )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!(
+                "Survival of the fittest {}",
+                if survival { ENABLED } else { DISABLED }
+            ),
+            format!("survival{start}"),
+        )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!(
+                "Only pick 1 black cards {}",
+                if single_pick { ENABLED } else { DISABLED }
+            ),
+            format!("single{start}"),
+        )]);
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            format!(
+                "Reveal submissions in recap {}",
+                if reveal_submissions {
+                    ENABLED
+                } else {
+                    DISABLED
+                }
+            ),
+            format!("reveal{start}"),
+        )]);
         keyboard.push(vec![InlineKeyboardButton::for_callback_data(
             format!(
                 "{} all packs",
@@ -75,12 +75,13 @@ where
     let msg = format!(
         "Player created{}\n\n{}",
         match chat.players {
-            1 => Cow::Borrowed(", you're the owner of this game, that means you're the only one who can use /settings and /close the game, you can start to play as soon as someone else joins"),
-            2 => Cow::Owned(format!(", you're the second one on this game, you can start playing by enabling {} from /settings", crate::RANDO_CARLISSIAN)),
-            3 => Cow::Owned(format!(", you're the third one on this game, you can now play freely without {}", crate::RANDO_CARLISSIAN)),
+            1 => Cow::Borrowed(", you're the owner of this game, that means you're the only one who can use /settings, /begin and /close the game, you can begin to play as soon as someone else joins"),
+            2 => Cow::Owned(format!(", you're the second one on this game, the owner can /begin the game by enabling {} from /settings", crate::RANDO_CARLISSIAN)),
+            3 => Cow::Owned(format!(", you're the third one on this game, the owner can now /begin the game without {}", crate::RANDO_CARLISSIAN)),
             _ => Cow::Borrowed(""),
         },
-        if chat.players + chat.rando_carlissian as i32 > 2 {
+        // a new player changes turns order
+        if chat.started {
             match chat.reset(&txn).await? {
                 Ok(msg) => msg,
                 Err(e) => return Ok(Err(StartError::from(e))),
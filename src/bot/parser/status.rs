@@ -14,6 +14,8 @@ use crate::{
 
 #[derive(thiserror::Error, Debug)]
 pub enum StatusError {
+    #[error("The game hasn't begun yet, the owner can /begin it")]
+    NotStarted,
     #[error("Not enough players in the game")]
     NotEnoughPlayers,
     #[error("No judge in game \\(this is a bug\\)")]
@@ -35,6 +37,10 @@ pub async fn execute<C>(
 where
     C: ConnectionTrait + StreamTrait,
 {
+    if !chat.started {
+        return Ok(Err(StatusError::NotStarted));
+    }
+
     if 3 > chat.players + chat.rando_carlissian as i32 {
         return Ok(Err(StatusError::NotEnoughPlayers));
     }
@@ -54,9 +60,9 @@ where
         })
         .await?;
 
-    let Some(judge) = judge else {
+    if judge.is_none() && !chat.god_is_dead {
         return Ok(Err(StatusError::NoJudge));
-    };
+    }
 
     if chat.rando_carlissian {
         players.push((0, Cow::Borrowed(crate::RANDO_CARLISSIAN)));
@@ -82,7 +88,8 @@ where
         )
         .await?;
 
-    let Some(judge_cards) = cards.get(&judge.id) else {
+    let holder = judge.as_ref().map_or(hand::NO_JUDGE, |judge| judge.id);
+    let Some(judge_cards) = cards.get(&holder) else {
         return Ok(Err(StatusError::NoBlackCard));
     };
     if judge_cards.len() != 1 {
@@ -97,10 +104,13 @@ where
     };
 
     let mut msg = format!(
-        "Turn {}\n\n*{}*\n\nJudge is {}",
+        "Turn {}\n\n*{}*\n\n{}",
         chat.turn,
         black_card.text(),
-        judge.tg_link()
+        match judge {
+            Some(judge) => format!("Judge is {}", judge.tg_link()),
+            None => String::from("God is dead, everyone votes the winner"),
+        }
     );
     for player in players {
         msg.push_str(&format!(
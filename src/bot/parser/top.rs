@@ -0,0 +1,116 @@
+use std::borrow::Cow;
+
+use chrono::Days;
+use sea_orm::{
+    sea_query::{Expr, Func},
+    ColumnTrait, ConnectionTrait, EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect,
+};
+use tgbot::{
+    api::Client,
+    types::{ParseMode, ReplyParameters, SendMessage},
+};
+
+use crate::{
+    entities::{chat, player, rating, score, season},
+    Error,
+};
+
+/// time span covered by the leaderboard
+pub enum Window {
+    Week,
+    Season(season::Period),
+    AllTime,
+}
+
+impl Window {
+    pub fn parse(arg: Option<&str>, period: season::Period) -> Self {
+        match arg {
+            Some("week") => Window::Week,
+            Some("all") => Window::AllTime,
+            _ => Window::Season(period),
+        }
+    }
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    message_id: i64,
+    chat: &chat::Model,
+    window: Window,
+) -> Result<(), Error>
+where
+    C: ConnectionTrait,
+{
+    let mut query = score::Entity::find();
+    let title = match window {
+        Window::Week => {
+            query = query.filter(score::Column::Week.eq(score::current_week()));
+            Cow::Borrowed("this week")
+        }
+        Window::Season(period) => {
+            // scores are weekly, the season starts with the week containing its first day
+            let (start, _) = period.current();
+            query = query.filter(score::Column::Week.gt(start - Days::new(7)));
+            Cow::Owned(format!("season {}", period.name(start)))
+        }
+        Window::AllTime => Cow::Borrowed("all time"),
+    };
+    // ranked by wins in the window, rating breaks ties,
+    // users without one yet have the initial rating
+    let rating = Expr::expr(Func::coalesce([
+        Expr::col((rating::Entity, rating::Column::Rating)).into(),
+        Expr::val(rating::INITIAL_RATING).into(),
+    ]))
+    .max();
+    let scores = query
+        .join(
+            JoinType::LeftJoin,
+            score::Entity::belongs_to(rating::Entity)
+                .from(score::Column::TelegramId)
+                .to(rating::Column::TelegramId)
+                .into(),
+        )
+        .select_only()
+        .column(score::Column::TelegramId)
+        .column_as(Expr::col(score::Column::GamesWon).sum(), "games_won")
+        .column_as(Expr::col(score::Column::RoundsWon).sum(), "rounds_won")
+        .column_as(rating.clone(), "rating")
+        .group_by(score::Column::TelegramId)
+        .order_by_desc(Expr::col(score::Column::RoundsWon).sum())
+        .order_by_desc(Expr::col(score::Column::GamesWon).sum())
+        .order_by_desc(rating)
+        .limit(20)
+        .into_tuple::<(i64, i64, i64, i32)>()
+        .all(conn)
+        .await?;
+
+    let names = player::tg_links(
+        conn,
+        scores.iter().map(|(telegram_id, _, _, _)| *telegram_id),
+    )
+    .await?;
+
+    let mut msg = format!("Global leaderboard, {title}\n");
+    for (position, (telegram_id, games_won, rounds_won, rating)) in scores.into_iter().enumerate() {
+        let Some(name) = names.get(&telegram_id) else {
+            continue;
+        };
+        msg.push_str(&format!(
+            "\n{}\\. {name} {games_won} game{} and {rounds_won} turn{} won, rating {rating}",
+            position + 1,
+            if games_won == 1 { "" } else { "s" },
+            if rounds_won == 1 { "" } else { "s" },
+        ));
+    }
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_parameters(ReplyParameters::new(message_id))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, StreamTrait, TransactionTrait,
+};
+use tgbot::{
+    api::Client,
+    types::{EditMessageReplyMarkup, InlineKeyboardButton, ParseMode, SendMessage, User},
+};
+
+use crate::{
+    entities::{card, chat, player, vote},
+    Error,
+};
+
+/// anonymous submissions, wagers excluded, ordered by their first card so that the order
+/// doesn't tell the players
+fn ballot(round: &chat::Round) -> Vec<(i32, Vec<&card::Model>)> {
+    let mut ballot = round
+        .submissions
+        .iter()
+        .filter(|((_, wager), _)| !wager)
+        .map(|((player_id, _), submission)| {
+            (
+                *player_id,
+                submission.iter().map(|(_, card)| card).collect::<Vec<_>>(),
+            )
+        })
+        .collect::<Vec<_>>();
+    ballot.sort_by_key(|(_, cards)| cards.first().map(|card| card.id));
+    ballot
+}
+
+fn keyboard(
+    chat: &chat::Model,
+    round: &chat::Round,
+    ballot: &[(i32, Vec<&card::Model>)],
+    tally: &HashMap<i32, i64>,
+) -> Vec<Vec<InlineKeyboardButton>> {
+    let mut keyboard = ballot
+        .iter()
+        .enumerate()
+        .map(|(position, (player_id, _))| {
+            vec![InlineKeyboardButton::for_callback_data(
+                format!(
+                    "{} 🗳 {}",
+                    position + 1,
+                    tally.get(player_id).copied().unwrap_or_default()
+                ),
+                format!("vote {} {player_id}", round.turn),
+            )]
+        })
+        .collect::<Vec<_>>();
+    // without a judge, absent players would stall the game
+    if chat.god_is_dead {
+        keyboard.push(vec![InlineKeyboardButton::for_callback_data(
+            "🔒 Close the vote",
+            format!("vote {} close", round.turn),
+        )]);
+    }
+    keyboard
+}
+
+/// posts the current turn's submissions for the people's choice vote
+pub async fn send<C>(client: &Client, conn: &C, chat: &chat::Model) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let Some(round) = chat.current_round(conn).await? else {
+        return Ok(());
+    };
+    let Some(black_card) = &round.black_card else {
+        return Ok(());
+    };
+
+    let ballot = ballot(&round);
+    let mut msg = String::from(if chat.god_is_dead {
+        "All players have choosen, now everyone can vote the winner:"
+    } else {
+        "People's choice, vote your favourite submission:"
+    });
+    for (position, (_, cards)) in ballot.iter().enumerate() {
+        msg.push_str(&format!(
+            "\n\n{}\\. {}",
+            position + 1,
+            black_card.fill(cards)
+        ));
+    }
+
+    client
+        .execute(
+            SendMessage::new(chat.telegram_id, msg)
+                .with_reply_markup(keyboard(chat, &round, &ballot, &HashMap::new()))
+                .with_parse_mode(ParseMode::MarkdownV2),
+        )
+        .await?;
+
+    Ok(())
+}
+
+pub async fn execute<C>(
+    client: &Client,
+    conn: &C,
+    user: &User,
+    message_id: i64,
+    chat: &chat::Model,
+    query_data: &str,
+) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait + TransactionTrait,
+{
+    let Some((Ok(turn), target)) = query_data
+        .strip_prefix("vote ")
+        .and_then(|data| data.split_once(' '))
+        .map(|(turn, target)| (turn.parse::<i32>(), target))
+    else {
+        return Ok(());
+    };
+
+    // votes are closed once the turn is over
+    if !(chat.peoples_choice || chat.god_is_dead) || turn != chat.turn {
+        return Ok(());
+    }
+
+    let Some(voter) = player::Entity::find()
+        .filter(
+            player::Column::TelegramId
+                .eq(i64::from(user.id))
+                .and(player::Column::ChatId.eq(chat.id)),
+        )
+        .one(conn)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    // the owner, or a majority of players having voted, can close the vote without waiting for everyone
+    if target == "close" {
+        let voted = vote::tally(conn, chat).await?.values().sum::<i64>();
+        if chat.god_is_dead && (chat.owner == Some(voter.id) || voted * 2 > chat.players as i64) {
+            return close(client, conn, chat).await;
+        }
+        return Ok(());
+    }
+
+    let Ok(player_id) = target.parse::<i32>() else {
+        return Ok(());
+    };
+    if voter.is_my_turn(chat) || voter.id == player_id {
+        return Ok(());
+    }
+
+    let Some(round) = chat.current_round(conn).await? else {
+        return Ok(());
+    };
+    if !round.submissions.contains_key(&(player_id, false)) {
+        return Ok(());
+    }
+
+    vote::cast(conn, chat, voter.id, player_id).await?;
+
+    let tally = vote::tally(conn, chat).await?;
+    // without a judge the turn is over when every player has voted, ties win together
+    if chat.god_is_dead && tally.values().sum::<i64>() >= chat.players as i64 {
+        return close(client, conn, chat).await;
+    }
+
+    client
+        .execute(
+            EditMessageReplyMarkup::for_chat_message(chat.telegram_id, message_id)
+                .with_reply_markup(keyboard(chat, &round, &ballot(&round), &tally)),
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// ends a judgeless turn, the most voted submissions win together, nobody wins without votes
+async fn close<C>(client: &Client, conn: &C, chat: &chat::Model) -> Result<(), Error>
+where
+    C: ConnectionTrait + StreamTrait + TransactionTrait,
+{
+    let winners = vote::favourites(conn, chat)
+        .await?
+        .into_iter()
+        .map(|player_id| (player_id, false))
+        .collect::<Vec<_>>();
+    if let Err(e) = super::choose::end_turn(client, conn, None, chat, &winners).await? {
+        super::choose::game_over(client, conn, chat, e).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDateTime, Utc};
+use sea_orm::{entity::prelude::*, ActiveValue, JoinType, QuerySelect};
+
+use super::{card, chat, hand, player};
+
+/// achievements unlocked by Telegram users
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "achievements")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub telegram_id: i64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub kind: Kind,
+    pub chat_id: i32,
+    pub unlocked_on: NaiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+pub enum Kind {
+    #[sea_orm(string_value = "pick_three")]
+    PickThree,
+    #[sea_orm(string_value = "rando_loser")]
+    RandoLoser,
+    #[sea_orm(string_value = "hat_trick")]
+    HatTrick,
+    #[sea_orm(string_value = "judge")]
+    Judge,
+    #[sea_orm(string_value = "veteran")]
+    Veteran,
+}
+
+const JUDGED_ROUNDS: usize = 50;
+const PLAYED_GAMES: usize = 100;
+
+impl Kind {
+    pub fn title(&self) -> &'static str {
+        match self {
+            Kind::PickThree => "Triple threat",
+            Kind::RandoLoser => "Outsmarted by a random card",
+            Kind::HatTrick => "Hat trick",
+            Kind::Judge => "Honourable judge",
+            Kind::Veteran => "Veteran",
+        }
+    }
+
+    pub fn descr(&self) -> String {
+        match self {
+            Kind::PickThree => String::from("win a turn with a pick 3 card"),
+            Kind::RandoLoser => format!("lose a turn to {}", crate::RANDO_CARLISSIAN),
+            Kind::HatTrick => String::from("win three turns in a row"),
+            Kind::Judge => format!("judge {JUDGED_ROUNDS} turns"),
+            Kind::Veteran => format!("play {PLAYED_GAMES} games"),
+        }
+    }
+}
+
+/// unlocks an achievement, returns the announcement if it wasn't already unlocked
+async fn unlock<C: ConnectionTrait>(
+    conn: &C,
+    player: &player::Model,
+    kind: Kind,
+) -> Result<Option<String>, DbErr> {
+    if Entity::find_by_id((player.telegram_id, kind))
+        .one(conn)
+        .await?
+        .is_some()
+    {
+        return Ok(None);
+    }
+
+    ActiveModel {
+        telegram_id: ActiveValue::Set(player.telegram_id),
+        kind: ActiveValue::Set(kind),
+        chat_id: ActiveValue::Set(player.chat_id),
+        unlocked_on: ActiveValue::Set(Utc::now().naive_utc()),
+    }
+    .insert(conn)
+    .await?;
+
+    Ok(Some(format!(
+        "🏅 {} unlocked *{}*: {}",
+        player.tg_link(),
+        kind.title(),
+        kind.descr()
+    )))
+}
+
+/// evaluates achievements at the end of a turn, before it gets incremented
+pub async fn on_turn<C: ConnectionTrait>(
+    conn: &C,
+    chat: &chat::Model,
+    judge: Option<&player::Model>,
+    winner_ids: &[i32],
+) -> Result<Vec<String>, DbErr> {
+    let mut unlocked = Vec::new();
+
+    let players = player::Entity::find()
+        .filter(player::Column::ChatId.eq(chat.id))
+        .all(conn)
+        .await?;
+
+    for winner in players
+        .iter()
+        .filter(|player| winner_ids.contains(&player.id))
+    {
+        if chat.pick >= 3 {
+            unlocked.extend(unlock(conn, winner, Kind::PickThree).await?);
+        }
+
+        let streak = hand::Entity::find()
+            .filter(
+                hand::Column::PlayerId
+                    .eq(winner.id)
+                    .and(hand::Column::Won.eq(true))
+                    .and(hand::Column::PlayedOnTurn.between(chat.turn - 2, chat.turn - 1)),
+            )
+            .select_only()
+            .column(hand::Column::PlayedOnTurn)
+            .distinct()
+            .into_tuple::<Option<i32>>()
+            .all(conn)
+            .await?;
+        if streak.len() == 2 {
+            unlocked.extend(unlock(conn, winner, Kind::HatTrick).await?);
+        }
+    }
+
+    if winner_ids.contains(&0) {
+        for player in players
+            .iter()
+            .filter(|player| Some(player.id) != judge.map(|judge| judge.id))
+        {
+            unlocked.extend(unlock(conn, player, Kind::RandoLoser).await?);
+        }
+    }
+
+    let Some(judge) = judge else {
+        return Ok(unlocked);
+    };
+
+    // judged turns are the black cards ever picked by this user
+    let judged = hand::Entity::find()
+        .join(
+            JoinType::InnerJoin,
+            hand::Entity::belongs_to(card::Entity)
+                .from(hand::Column::CardId)
+                .to(card::Column::Id)
+                .into(),
+        )
+        .join(
+            JoinType::InnerJoin,
+            hand::Entity::belongs_to(player::Entity)
+                .from(hand::Column::PlayerId)
+                .to(player::Column::Id)
+                .into(),
+        )
+        .filter(
+            player::Column::TelegramId
+                .eq(judge.telegram_id)
+                .and(card::Column::Color.eq(card::Color::Black)),
+        )
+        .select_only()
+        .column_as(hand::Column::Id.count(), "count")
+        .into_tuple::<Option<i64>>()
+        .one(conn)
+        .await?
+        .flatten()
+        .unwrap_or_default();
+    if judged >= JUDGED_ROUNDS as i64 {
+        unlocked.extend(unlock(conn, judge, Kind::Judge).await?);
+    }
+
+    Ok(unlocked)
+}
+
+/// evaluates achievements once a game is closed
+pub async fn on_close<C: ConnectionTrait>(
+    conn: &C,
+    chat: &chat::Model,
+) -> Result<Vec<String>, DbErr> {
+    let mut unlocked = Vec::new();
+
+    let players = player::Entity::find()
+        .filter(player::Column::ChatId.eq(chat.id))
+        .all(conn)
+        .await?;
+
+    let games = player::Entity::find()
+        .filter(player::Column::TelegramId.is_in(players.iter().map(|player| player.telegram_id)))
+        .all(conn)
+        .await?;
+    let closed = chat::Entity::find()
+        .filter(
+            chat::Column::Id
+                .is_in(games.iter().map(|player| player.chat_id))
+                .and(chat::Column::EndDate.is_not_null()),
+        )
+        .select_only()
+        .column(chat::Column::Id)
+        .into_tuple::<i32>()
+        .all(conn)
+        .await?;
+    let played = games
+        .iter()
+        .filter(|player| closed.contains(&player.chat_id))
+        .fold(HashMap::<_, usize>::new(), |mut played, player| {
+            *played.entry(player.telegram_id).or_default() += 1;
+            played
+        });
+
+    for player in &players {
+        if played.get(&player.telegram_id).copied().unwrap_or_default() >= PLAYED_GAMES {
+            unlocked.extend(unlock(conn, player, Kind::Veteran).await?);
+        }
+    }
+
+    Ok(unlocked)
+}
@@ -17,6 +17,10 @@ impl Model {
     pub fn text(&self) -> String {
         crate::utils::escape_markdown(&self.text)
     }
+
+    pub fn plain_text(&self) -> &str {
+        &self.text
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -56,6 +60,55 @@ impl Model {
         }
         descr
     }
+
+    /// black card text with blanks replaced by given answers, in order,
+    /// answers exceeding the number of blanks are appended at the end
+    pub fn fill(&self, answers: &[&Model]) -> String {
+        self.fill_with(
+            answers,
+            |text| format!("*{}*", crate::utils::escape_markdown(text)),
+            |text| crate::utils::escape_markdown(text),
+        )
+    }
+
+    /// same as `fill`, but without markdown, to be used in inline results titles
+    pub fn fill_plain(&self, answers: &[&Model]) -> String {
+        self.fill_with(answers, str::to_owned, str::to_owned)
+    }
+
+    fn fill_with(
+        &self,
+        answers: &[&Model],
+        answer_fmt: impl Fn(&str) -> String,
+        text_fmt: impl Fn(&str) -> String,
+    ) -> String {
+        let mut answers = answers.iter().map(|answer| answer.text.trim());
+
+        let mut chunks = self.text.split('_');
+        let mut out = text_fmt(chunks.next().unwrap_or_default());
+        // consecutive underscores are a single blank
+        let mut in_blank = false;
+        for chunk in chunks {
+            if !in_blank {
+                match answers.next() {
+                    // white cards are full sentences, drop final dot when embedded
+                    Some(answer) => {
+                        out.push_str(&answer_fmt(answer.strip_suffix('.').unwrap_or(answer)))
+                    }
+                    None => out.push_str(&text_fmt("_")),
+                }
+            }
+            in_blank = chunk.is_empty();
+            out.push_str(&text_fmt(chunk));
+        }
+
+        let rest = answers.map(&answer_fmt).collect::<Vec<_>>();
+        if !rest.is_empty() {
+            out.push(' ');
+            out.push_str(&rest.join(&text_fmt(" - ")));
+        }
+        out
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
@@ -66,3 +119,69 @@ pub enum Color {
     #[sea_orm(string_value = "white")]
     White,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, Model};
+
+    fn card(color: Color, text: &str) -> Model {
+        Model {
+            id: 0,
+            pack_id: 0,
+            color,
+            pick: None,
+            text: text.to_owned(),
+        }
+    }
+
+    fn fill(black: &str, whites: &[&str]) -> String {
+        let whites = whites
+            .iter()
+            .map(|text| card(Color::White, text))
+            .collect::<Vec<_>>();
+        card(Color::Black, black).fill_plain(&whites.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn blanks_in_order() {
+        assert_eq!(
+            fill("_ and _ walk into a bar.", &["A priest.", "A rabbi."]),
+            "A priest and A rabbi walk into a bar."
+        );
+    }
+
+    #[test]
+    fn consecutive_underscores() {
+        assert_eq!(
+            fill("I drink to forget ____.", &["Bees?"]),
+            "I drink to forget Bees?."
+        );
+        assert_eq!(fill("___ and __", &["Me.", "You."]), "Me and You");
+    }
+
+    #[test]
+    fn missing_answers() {
+        assert_eq!(fill("_ and ___", &["Me."]), "Me and _");
+    }
+
+    #[test]
+    fn extra_answers() {
+        assert_eq!(
+            fill("What's my secret power?", &["Bees.", "Fire."]),
+            "What's my secret power? Bees. - Fire."
+        );
+        assert_eq!(
+            fill("_ is life.", &["Bees.", "Fire."]),
+            "Bees is life. Fire."
+        );
+    }
+
+    #[test]
+    fn markdown() {
+        let whites = [card(Color::White, "Bees.")];
+        assert_eq!(
+            card(Color::Black, "Why _?").fill(&whites.iter().collect::<Vec<_>>()),
+            "Why *Bees*?"
+        );
+    }
+}
@@ -1,16 +1,21 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    future,
+};
 
 use chrono::{NaiveDateTime, Utc};
 use futures_util::TryStreamExt;
+use rand::Rng;
 use sea_orm::{
-    entity::prelude::*, ActiveValue, DatabaseTransaction, QueryOrder, QuerySelect, StreamTrait,
-    TransactionTrait,
+    entity::prelude::*, sea_query::SimpleExpr, ActiveValue, DatabaseTransaction, QueryOrder,
+    QuerySelect, StreamTrait, TransactionTrait,
 };
 use tgbot::types::Chat;
 
 use crate::Error;
 
-use super::{chat_pack, hand, player};
+use super::{achievement, card, chat_default, chat_pack, hand, player, rating, score};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "chats")]
@@ -25,6 +30,55 @@ pub struct Model {
     pub turn: i32,
     pub rando_carlissian: bool,
     pub pick: i32,
+    pub started: bool,
+    pub global_leaderboard: bool,
+    pub peoples_choice: bool,
+    pub god_is_dead: bool,
+    pub rotation: Rotation,
+    /// current judge's player turn, when not rotating round-robin
+    pub judge_turn: i32,
+    pub reboot: bool,
+    /// free discards per player
+    pub discards: i32,
+    pub gambling: bool,
+    pub survival: bool,
+    /// excludes black cards with more than one blank
+    pub single_pick: bool,
+    /// lists every submission with its author in the turn recap
+    pub reveal_submissions: bool,
+    /// last turn the judge redrawn the black card
+    pub redrawn_turn: i32,
+}
+
+/// how the judge changes between turns
+#[derive(Copy, Clone, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+pub enum Rotation {
+    #[sea_orm(string_value = "round_robin")]
+    RoundRobin,
+    #[sea_orm(string_value = "winner")]
+    Winner,
+    #[sea_orm(string_value = "random")]
+    Random,
+}
+
+impl Rotation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rotation::RoundRobin => "round-robin",
+            Rotation::Winner => "winner judges next",
+            Rotation::Random => "random",
+        }
+    }
+
+    /// policy following this one, to cycle through them
+    pub fn next(&self) -> Self {
+        match self {
+            Rotation::RoundRobin => Rotation::Winner,
+            Rotation::Winner => Rotation::Random,
+            Rotation::Random => Rotation::RoundRobin,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -58,7 +112,12 @@ impl Related<hand::Entity> for Entity {
 impl ActiveModelBehavior for ActiveModel {}
 
 impl Model {
+    /// judge's player turn
     pub fn next_player_turn(&self) -> i32 {
+        if self.rotation != Rotation::RoundRobin {
+            return self.judge_turn;
+        }
+
         let turn = self.turn % self.players;
         if turn == 0 {
             self.players
@@ -67,6 +126,16 @@ impl Model {
         }
     }
 
+    /// next turn's judge player turn, given this turn's winner player turn if any
+    pub fn next_judge_turn(&self, winner_turn: Option<i32>) -> i32 {
+        let round_robin = self.next_player_turn() % self.players.max(1) + 1;
+        match self.rotation {
+            Rotation::RoundRobin => round_robin,
+            Rotation::Winner => winner_turn.unwrap_or(round_robin),
+            Rotation::Random => rand::thread_rng().gen_range(1..=self.players.max(1)),
+        }
+    }
+
     pub async fn reset(
         &self,
         txn: &DatabaseTransaction,
@@ -81,6 +150,14 @@ impl Model {
             )
             .all(txn)
             .await?;
+        // wagered points go back to the gamblers
+        let mut gamblers = hands
+            .iter()
+            .filter(|hand| hand.wager && hand.played_on_turn == Some(self.turn))
+            .map(|hand| hand.player_id)
+            .collect::<Vec<_>>();
+        gamblers.sort_unstable();
+        gamblers.dedup();
         for hand in hands {
             if hand.picked_on_turn == self.turn {
                 hand::ActiveModel {
@@ -93,12 +170,25 @@ impl Model {
                 hand::ActiveModel {
                     id: ActiveValue::Set(hand.id),
                     played_on_turn: ActiveValue::Set(None),
+                    seq: ActiveValue::Set(0),
+                    wager: ActiveValue::Set(false),
+                    eliminated: ActiveValue::Set(false),
                     ..Default::default()
                 }
                 .update(txn)
                 .await?;
             }
         }
+        if !gamblers.is_empty() {
+            player::Entity::update_many()
+                .col_expr(
+                    player::Column::Points,
+                    Expr::col(player::Column::Points).add(1),
+                )
+                .filter(player::Column::Id.is_in(gamblers))
+                .exec(txn)
+                .await?;
+        }
 
         let players = player::Entity::find()
             .filter(player::Column::ChatId.eq(self.id))
@@ -116,6 +206,14 @@ impl Model {
                 Err(e) => return Ok(Err(ChatError::from(e))),
             }
         }
+        let black_card = if self.god_is_dead {
+            match hand::pick(txn, hand::NO_JUDGE, self.id, self.turn, true).await? {
+                Ok(card) => card.map(|card| (None, card)),
+                Err(e) => return Ok(Err(ChatError::from(e))),
+            }
+        } else {
+            black_card.map(|(judge, card)| (Some(judge), card))
+        };
 
         Ok(if let Some((judge, card)) = black_card {
             let pick = card.pick();
@@ -136,36 +234,183 @@ impl Model {
             }
 
             Ok(format!(
-                "Turn {}\n\n{}\n\nJudge is {}",
+                "Turn {}\n\n{}\n\n{}",
                 self.turn,
                 card.descr(),
-                judge.tg_link(),
+                match judge {
+                    Some(judge) => format!("Judge is {}", judge.tg_link()),
+                    None => String::from("God is dead, everyone votes the winner"),
+                },
             ))
         } else {
             Err(ChatError::NoBlackCard)
         })
     }
 
-    pub async fn close<C>(&self, conn: &C) -> Result<Result<String, ChatError>, Error>
+    /// copies game settings, enabled packs included, to another game
+    pub async fn copy_settings<C>(&self, conn: &C, chat_id: i32) -> Result<Model, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let chat = ActiveModel {
+            id: ActiveValue::Set(chat_id),
+            rando_carlissian: ActiveValue::Set(self.rando_carlissian),
+            global_leaderboard: ActiveValue::Set(self.global_leaderboard),
+            peoples_choice: ActiveValue::Set(self.peoples_choice),
+            god_is_dead: ActiveValue::Set(self.god_is_dead),
+            rotation: ActiveValue::Set(self.rotation),
+            reboot: ActiveValue::Set(self.reboot),
+            discards: ActiveValue::Set(self.discards),
+            gambling: ActiveValue::Set(self.gambling),
+            survival: ActiveValue::Set(self.survival),
+            single_pick: ActiveValue::Set(self.single_pick),
+            reveal_submissions: ActiveValue::Set(self.reveal_submissions),
+            ..Default::default()
+        }
+        .update(conn)
+        .await?;
+
+        chat_pack::Entity::delete_many()
+            .filter(chat_pack::Column::ChatId.eq(chat_id))
+            .exec(conn)
+            .await?;
+
+        let packs = chat_pack::Entity::find()
+            .filter(chat_pack::Column::ChatId.eq(self.id))
+            .all(conn)
+            .await?;
+        for pack in packs {
+            chat_pack::ActiveModel {
+                chat_id: ActiveValue::Set(chat_id),
+                pack_id: ActiveValue::Set(pack.pack_id),
+            }
+            .insert(conn)
+            .await?;
+        }
+
+        Ok(chat)
+    }
+
+    /// completed turns, in order
+    pub async fn rounds<C>(&self, conn: &C) -> Result<Vec<Round>, DbErr>
     where
         C: ConnectionTrait + StreamTrait,
     {
-        let stream = player::Entity::find()
+        self.find_rounds(conn, hand::Column::PlayedOnTurn.lt(self.turn))
+            .await
+    }
+
+    /// cards played so far in the current turn
+    pub async fn current_round<C>(&self, conn: &C) -> Result<Option<Round>, DbErr>
+    where
+        C: ConnectionTrait + StreamTrait,
+    {
+        Ok(self
+            .find_rounds(conn, hand::Column::PlayedOnTurn.eq(self.turn))
+            .await?
+            .pop())
+    }
+
+    /// every player submitted this turn's cards, wagers excluded
+    pub async fn all_played<C>(&self, conn: &C) -> Result<bool, DbErr>
+    where
+        C: ConnectionTrait,
+    {
+        let played = hand::Entity::find()
             .filter(
-                player::Column::ChatId
+                hand::Column::ChatId
                     .eq(self.id)
-                    .and(player::Column::Points.gt(0)),
+                    .and(hand::Column::PlayedOnTurn.eq(Some(self.turn)))
+                    .and(hand::Column::PlayerId.gt(0))
+                    .and(hand::Column::Wager.eq(false)),
             )
+            .select_only()
+            .column_as(hand::Column::Id.count(), "count")
+            .into_tuple::<Option<i64>>()
+            .one(conn)
+            .await?
+            .flatten()
+            .unwrap_or_default();
+
+        Ok(if self.god_is_dead {
+            played >= self.players as i64 * self.pick as i64
+        } else {
+            // judge always plays only 1 card
+            played > (self.players as i64 - 1) * self.pick as i64
+        })
+    }
+
+    async fn find_rounds<C>(&self, conn: &C, filter: SimpleExpr) -> Result<Vec<Round>, DbErr>
+    where
+        C: ConnectionTrait + StreamTrait,
+    {
+        let hands = hand::Entity::find()
+            .filter(hand::Column::ChatId.eq(self.id).and(filter))
+            .order_by_asc(hand::Column::PlayedOnTurn)
+            .order_by_asc(hand::Column::Seq)
+            .all(conn)
+            .await?;
+        let stream = card::Entity::find()
+            .filter(card::Column::Id.is_in(hands.iter().map(|hand| hand.card_id)))
+            .stream(conn)
+            .await?;
+        let mut cards = stream
+            .map_ok(|card| (card.id, card))
+            .try_collect::<HashMap<_, _>>()
+            .await?;
+
+        let mut rounds = BTreeMap::<_, Round>::new();
+        for hand in hands {
+            let (Some(turn), Some(card)) = (hand.played_on_turn, cards.remove(&hand.card_id))
+            else {
+                continue;
+            };
+            let round = rounds.entry(turn).or_insert_with(|| Round {
+                turn,
+                black_card: None,
+                judge: None,
+                submissions: BTreeMap::new(),
+            });
+            if card.color == card::Color::Black {
+                round.black_card = Some(card);
+                round.judge = (hand.player_id != hand::NO_JUDGE).then_some(hand.player_id);
+            } else {
+                round
+                    .submissions
+                    .entry((hand.player_id, hand.wager))
+                    .or_default()
+                    .push((hand, card));
+            }
+        }
+
+        Ok(rounds.into_values().collect())
+    }
+
+    /// players points, Telegram user id and name, ordered by points,
+    /// with Rando Carlissian if enabled
+    pub async fn scoreboard<C>(&self, conn: &C) -> Result<Vec<(i64, i64, Cow<'static, str>)>, DbErr>
+    where
+        C: ConnectionTrait + StreamTrait,
+    {
+        let stream = player::Entity::find()
+            .filter(player::Column::ChatId.eq(self.id))
             .order_by_desc(player::Column::Points)
             .stream(conn)
             .await?;
 
         let mut players = stream
-            .map_ok(|player| (player.points as i64, Cow::Owned(player.tg_link())))
+            .map_ok(|player| {
+                (
+                    player.points as i64,
+                    player.telegram_id,
+                    Cow::Owned(player.tg_link()),
+                )
+            })
             .try_collect::<Vec<_>>()
             .await?;
 
         if self.rando_carlissian {
+            // on multi-pick turns there are more won hands, one per card
             let won = hand::Entity::find()
                 .filter(
                     hand::Column::ChatId
@@ -174,18 +419,33 @@ impl Model {
                         .and(hand::Column::Won.eq(true)),
                 )
                 .select_only()
-                .column_as(hand::Column::Id.count(), "ids")
+                .column_as(
+                    Expr::col(hand::Column::PlayedOnTurn).count_distinct(),
+                    "turns",
+                )
                 .into_tuple::<Option<i64>>()
                 .one(conn)
                 .await?
                 .flatten()
                 .unwrap_or_default();
-            if won > 0 {
-                players.push((won, Cow::Borrowed(crate::RANDO_CARLISSIAN)));
-                players.sort_by(|(points_a, _), (points_b, _)| points_b.cmp(points_a));
-            }
+            players.push((won, 0, Cow::Borrowed(crate::RANDO_CARLISSIAN)));
+            players.sort_by(|(points_a, _, _), (points_b, _, _)| points_b.cmp(points_a));
         }
 
+        Ok(players)
+    }
+
+    pub async fn close<C>(&self, conn: &C) -> Result<Result<String, ChatError>, Error>
+    where
+        C: ConnectionTrait + StreamTrait,
+    {
+        let mut players = self.scoreboard(conn).await?;
+        let standings = players
+            .iter()
+            .map(|(points, telegram_id, _)| (*telegram_id, *points))
+            .collect::<Vec<_>>();
+        players.retain(|(points, _, _)| *points > 0);
+
         if players.is_empty() {
             return Ok(Err(ChatError::Empty));
         }
@@ -198,13 +458,20 @@ impl Model {
         .update(conn)
         .await?;
 
+        rating::update(conn, &standings).await?;
+        if self.global_leaderboard {
+            for telegram_id in winners(&standings).filter(|telegram_id| *telegram_id > 0) {
+                score::add(conn, telegram_id, 0, 1).await?;
+            }
+        }
+
         let winner_points = players[0].0;
         let winners = players
             .into_iter()
-            .map_while(|(points, player)| (points == winner_points).then_some(player))
+            .map_while(|(points, _, player)| (points == winner_points).then_some(player))
             .collect::<Vec<_>>();
 
-        Ok(Ok(format!(
+        let mut msg = format!(
             "After {} turns the winner{} {} with {} points{}",
             self.turn - 1,
             if winners.len() > 1 { "s are" } else { " is" },
@@ -215,7 +482,39 @@ impl Model {
             } else {
                 ""
             }
-        )))
+        );
+        for unlocked in achievement::on_close(conn, self).await? {
+            msg.push_str("\n\n");
+            msg.push_str(&unlocked);
+        }
+
+        Ok(Ok(msg))
+    }
+}
+
+/// cards played in a turn
+pub struct Round {
+    pub turn: i32,
+    pub black_card: Option<card::Model>,
+    /// judge player id
+    pub judge: Option<i32>,
+    /// played cards by player id and wager, ordered by seq
+    pub submissions: BTreeMap<(i32, bool), Vec<(hand::Model, card::Model)>>,
+}
+
+impl Round {
+    /// winning player ids and cards, more than one on ties
+    pub fn winners(&self) -> Vec<(i32, Vec<&card::Model>)> {
+        self.submissions
+            .iter()
+            .filter(|(_, submission)| submission.iter().any(|(hand, _)| hand.won))
+            .map(|((player_id, _), submission)| {
+                (
+                    *player_id,
+                    submission.iter().map(|(_, card)| card).collect(),
+                )
+            })
+            .collect()
     }
 }
 
@@ -258,17 +557,90 @@ where
 
     let txn = conn.begin().await?;
 
-    let chat = ActiveModel {
+    let defaults = chat_default::Entity::find_by_id(telegram_id)
+        .one(&txn)
+        .await?;
+
+    let mut chat = ActiveModel {
         telegram_id: ActiveValue::Set(telegram_id),
         start_date: ActiveValue::Set(Utc::now().naive_utc()),
         ..Default::default()
+    };
+    if let Some(defaults) = &defaults {
+        defaults.apply(&mut chat);
     }
-    .insert(&txn)
-    .await?;
+    let chat = chat.insert(&txn).await?;
 
-    super::chat_pack::init(&txn, chat.id).await?;
+    if let Some(defaults) = &defaults {
+        defaults.init_packs(&txn, chat.id).await?;
+    } else {
+        chat_pack::init(&txn, chat.id).await?;
+    }
 
     txn.commit().await?;
 
     Ok(Ok(chat))
 }
+
+/// final standings of given games, as Telegram user id and points ordered by points,
+/// Rando Carlissian uses id 0
+pub async fn standings<C>(
+    conn: &C,
+    chat_ids: &[i32],
+) -> Result<HashMap<i32, Vec<(i64, i64)>>, DbErr>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let stream = player::Entity::find()
+        .filter(player::Column::ChatId.is_in(chat_ids.iter().copied()))
+        .stream(conn)
+        .await?;
+    let mut standings = stream
+        .try_fold(HashMap::<_, Vec<_>>::new(), |mut standings, player| {
+            standings
+                .entry(player.chat_id)
+                .or_default()
+                .push((player.telegram_id, player.points as i64));
+            future::ready(Ok(standings))
+        })
+        .await?;
+
+    // on multi-pick turns there are more won hands, one per card
+    let rando = hand::Entity::find()
+        .filter(
+            hand::Column::ChatId
+                .is_in(chat_ids.iter().copied())
+                .and(hand::Column::PlayerId.eq(0))
+                .and(hand::Column::Won.eq(true)),
+        )
+        .select_only()
+        .column(hand::Column::ChatId)
+        .column_as(
+            Expr::col(hand::Column::PlayedOnTurn).count_distinct(),
+            "turns",
+        )
+        .group_by(hand::Column::ChatId)
+        .into_tuple::<(i32, i64)>()
+        .all(conn)
+        .await?;
+    for (chat_id, won) in rando {
+        standings.entry(chat_id).or_default().push((0, won));
+    }
+
+    for players in standings.values_mut() {
+        players.sort_by(|(_, points_a), (_, points_b)| points_b.cmp(points_a));
+    }
+
+    Ok(standings)
+}
+
+/// Telegram user ids of the winners of a game, given its standings
+pub fn winners(standings: &[(i64, i64)]) -> impl Iterator<Item = i64> + '_ {
+    let winner_points = standings
+        .first()
+        .map(|(_, points)| *points)
+        .unwrap_or_default();
+    standings.iter().map_while(move |(telegram_id, points)| {
+        (*points > 0 && *points == winner_points).then_some(*telegram_id)
+    })
+}
@@ -0,0 +1,132 @@
+use sea_orm::{entity::prelude::*, ActiveValue, QuerySelect};
+
+use super::{chat, chat_default_pack, chat_pack};
+
+/// settings inherited by every new game in the same Telegram chat
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "chat_defaults")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub telegram_id: i64,
+    pub rando_carlissian: bool,
+    pub global_leaderboard: bool,
+    pub peoples_choice: bool,
+    pub god_is_dead: bool,
+    pub rotation: chat::Rotation,
+    pub reboot: bool,
+    pub discards: i32,
+    pub gambling: bool,
+    pub survival: bool,
+    pub single_pick: bool,
+    pub reveal_submissions: bool,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "chat_default_pack::Entity")]
+    Pack,
+}
+
+impl Related<chat_default_pack::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Pack.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// applies defaults to a new game
+    pub fn apply(&self, chat: &mut chat::ActiveModel) {
+        chat.rando_carlissian = ActiveValue::Set(self.rando_carlissian);
+        chat.global_leaderboard = ActiveValue::Set(self.global_leaderboard);
+        chat.peoples_choice = ActiveValue::Set(self.peoples_choice);
+        chat.god_is_dead = ActiveValue::Set(self.god_is_dead);
+        chat.rotation = ActiveValue::Set(self.rotation);
+        chat.reboot = ActiveValue::Set(self.reboot);
+        chat.discards = ActiveValue::Set(self.discards);
+        chat.gambling = ActiveValue::Set(self.gambling);
+        chat.survival = ActiveValue::Set(self.survival);
+        chat.single_pick = ActiveValue::Set(self.single_pick);
+        chat.reveal_submissions = ActiveValue::Set(self.reveal_submissions);
+    }
+
+    /// enables default packs on a new game
+    pub async fn init_packs<C: ConnectionTrait>(
+        &self,
+        conn: &C,
+        chat_id: i32,
+    ) -> Result<(), DbErr> {
+        let packs = chat_default_pack::Entity::find()
+            .filter(chat_default_pack::Column::TelegramId.eq(self.telegram_id))
+            .select_only()
+            .column(chat_default_pack::Column::PackId)
+            .into_tuple::<i32>()
+            .all(conn)
+            .await?;
+
+        for pack_id in packs {
+            chat_pack::ActiveModel {
+                chat_id: ActiveValue::Set(chat_id),
+                pack_id: ActiveValue::Set(pack_id),
+            }
+            .insert(conn)
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+/// stores current game settings as defaults for next games in the same Telegram chat
+pub async fn save<C: ConnectionTrait>(conn: &C, chat_id: i32) -> Result<(), DbErr> {
+    let Some(chat) = chat::Entity::find_by_id(chat_id).one(conn).await? else {
+        return Ok(());
+    };
+
+    let model = ActiveModel {
+        telegram_id: ActiveValue::Set(chat.telegram_id),
+        rando_carlissian: ActiveValue::Set(chat.rando_carlissian),
+        global_leaderboard: ActiveValue::Set(chat.global_leaderboard),
+        peoples_choice: ActiveValue::Set(chat.peoples_choice),
+        god_is_dead: ActiveValue::Set(chat.god_is_dead),
+        rotation: ActiveValue::Set(chat.rotation),
+        reboot: ActiveValue::Set(chat.reboot),
+        discards: ActiveValue::Set(chat.discards),
+        gambling: ActiveValue::Set(chat.gambling),
+        survival: ActiveValue::Set(chat.survival),
+        single_pick: ActiveValue::Set(chat.single_pick),
+        reveal_submissions: ActiveValue::Set(chat.reveal_submissions),
+    };
+    if Entity::find_by_id(chat.telegram_id)
+        .one(conn)
+        .await?
+        .is_some()
+    {
+        model.update(conn).await?;
+    } else {
+        model.insert(conn).await?;
+    }
+
+    chat_default_pack::Entity::delete_many()
+        .filter(chat_default_pack::Column::TelegramId.eq(chat.telegram_id))
+        .exec(conn)
+        .await?;
+
+    let packs = chat_pack::Entity::find()
+        .filter(chat_pack::Column::ChatId.eq(chat.id))
+        .select_only()
+        .column(chat_pack::Column::PackId)
+        .into_tuple::<i32>()
+        .all(conn)
+        .await?;
+    for pack_id in packs {
+        chat_default_pack::ActiveModel {
+            telegram_id: ActiveValue::Set(chat.telegram_id),
+            pack_id: ActiveValue::Set(pack_id),
+        }
+        .insert(conn)
+        .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,26 @@
+use sea_orm::entity::prelude::*;
+
+use super::chat_default;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "chat_default_packs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub telegram_id: i64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub pack_id: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_one = "chat_default::Entity")]
+    Default,
+}
+
+impl Related<chat_default::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Default.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,89 @@
+use sea_orm::{entity::prelude::*, ActiveValue};
+
+use super::{chat, hall_of_fame_vote};
+
+/// winning combinations of every turn
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "hall_of_fame")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub telegram_id: i64,
+    pub chat_id: i32,
+    pub turn: i32,
+    pub player_id: i32,
+    /// black card filled with winning white cards, already markdown formatted
+    pub text: String,
+    pub votes: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "hall_of_fame_vote::Entity")]
+    Vote,
+}
+
+impl Related<hall_of_fame_vote::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Vote.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub async fn insert<C: ConnectionTrait>(
+    conn: &C,
+    chat: &chat::Model,
+    player_id: i32,
+    text: String,
+) -> Result<Model, DbErr> {
+    ActiveModel {
+        telegram_id: ActiveValue::Set(chat.telegram_id),
+        chat_id: ActiveValue::Set(chat.id),
+        turn: ActiveValue::Set(chat.turn),
+        player_id: ActiveValue::Set(player_id),
+        text: ActiveValue::Set(text),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await
+}
+
+/// upvotes a combination from its chat, every user can vote only once
+pub async fn vote<C: ConnectionTrait>(
+    conn: &C,
+    id: i32,
+    chat: &chat::Model,
+    telegram_id: i64,
+) -> Result<Option<Model>, DbErr> {
+    let Some(model) = Entity::find_by_id(id).one(conn).await? else {
+        return Ok(None);
+    };
+    // buttons forwarded to other chats don't count
+    if model.telegram_id != chat.telegram_id {
+        return Ok(None);
+    }
+
+    if hall_of_fame_vote::Entity::find_by_id((id, telegram_id))
+        .one(conn)
+        .await?
+        .is_some()
+    {
+        return Ok(None);
+    }
+
+    hall_of_fame_vote::ActiveModel {
+        hall_of_fame_id: ActiveValue::Set(id),
+        telegram_id: ActiveValue::Set(telegram_id),
+    }
+    .insert(conn)
+    .await?;
+
+    Entity::update_many()
+        .col_expr(Column::Votes, Expr::col(Column::Votes).add(1))
+        .filter(Column::Id.eq(id))
+        .exec(conn)
+        .await?;
+
+    Entity::find_by_id(id).one(conn).await
+}
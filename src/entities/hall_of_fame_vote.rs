@@ -0,0 +1,26 @@
+use sea_orm::entity::prelude::*;
+
+use super::hall_of_fame;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "hall_of_fame_votes")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub hall_of_fame_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub telegram_id: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_one = "hall_of_fame::Entity")]
+    HallOfFame,
+}
+
+impl Related<hall_of_fame::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::HallOfFame.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
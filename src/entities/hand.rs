@@ -5,6 +5,9 @@ use crate::Error;
 
 use super::{card, chat, chat_pack, player};
 
+/// owner of the black card when there's no judge
+pub const NO_JUDGE: i32 = -1;
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "hands")]
 pub struct Model {
@@ -17,6 +20,12 @@ pub struct Model {
     pub played_on_turn: Option<i32>,
     pub seq: i32,
     pub won: bool,
+    /// thrown away unplayed, it won't come back
+    pub discarded: bool,
+    /// extra submission a point has been wagered on
+    pub wager: bool,
+    /// cut by the judge in survival of the fittest mode
+    pub eliminated: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -94,15 +103,22 @@ where
         .await?;
 
     let black_cards = if pick_black {
-        let black_cards = card::Entity::find()
-            .filter(
-                card::Column::Color
-                    .eq(card::Color::Black)
-                    .and(card::Column::Id.is_not_in(already_picked.clone()))
-                    .and(card::Column::PackId.is_in(enabled_packs.clone())),
-            )
-            .all(conn)
-            .await?;
+        let single_pick = chat::Entity::find_by_id(chat_id)
+            .select_only()
+            .column(chat::Column::SinglePick)
+            .into_tuple::<bool>()
+            .one(conn)
+            .await?
+            .unwrap_or_default();
+        let mut filter = card::Column::Color
+            .eq(card::Color::Black)
+            .and(card::Column::Id.is_not_in(already_picked.clone()))
+            .and(card::Column::PackId.is_in(enabled_packs.clone()));
+        if single_pick {
+            filter = filter.and(card::Column::Pick.is_null().or(card::Column::Pick.lte(1)));
+        }
+
+        let black_cards = card::Entity::find().filter(filter).all(conn).await?;
 
         if black_cards.is_empty() {
             return Ok(Err(PickError::NoMoreBlackCards));
@@ -135,7 +151,8 @@ where
             .filter(
                 Column::PlayerId
                     .eq(player_id)
-                    .and(Column::PlayedOnTurn.is_null()),
+                    .and(Column::PlayedOnTurn.is_null())
+                    .and(Column::Discarded.eq(false)),
             )
             .select_only()
             .column_as(Column::Id.count(), "ids")
@@ -144,8 +161,11 @@ where
             .await?
             .flatten()
             .unwrap_or_default()
-    } else {
+    } else if player_id == 0 {
         9
+    } else {
+        // black card only
+        10
     };
 
     let mut rng = rand::thread_rng();
@@ -1,6 +1,16 @@
+pub mod achievement;
 pub mod card;
 pub mod chat;
+pub mod chat_default;
+pub mod chat_default_pack;
 pub mod chat_pack;
+pub mod hall_of_fame;
+pub mod hall_of_fame_vote;
 pub mod hand;
 pub mod pack;
 pub mod player;
+pub mod rating;
+pub mod score;
+pub mod season;
+pub mod season_standing;
+pub mod vote;
@@ -20,6 +20,10 @@ impl Model {
     pub fn name(&self) -> String {
         crate::utils::escape_markdown(&self.name)
     }
+
+    pub fn plain_name(&self) -> &str {
+        &self.name
+    }
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
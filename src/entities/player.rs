@@ -1,4 +1,6 @@
-use sea_orm::{entity::prelude::*, ActiveValue, QuerySelect};
+use std::collections::HashMap;
+
+use sea_orm::{entity::prelude::*, ActiveValue, QueryOrder, QuerySelect};
 
 use super::{chat, hand};
 
@@ -12,6 +14,8 @@ pub struct Model {
     name: String,
     pub turn: i32,
     pub points: i32,
+    /// discards used in this game
+    pub discards: i32,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -38,11 +42,28 @@ impl ActiveModelBehavior for ActiveModel {}
 
 impl Model {
     pub fn is_my_turn(&self, chat: &chat::Model) -> bool {
-        let mut turn = chat.turn % chat.players;
-        if turn == 0 {
-            turn = chat.players;
+        // there's no judge when god is dead
+        if chat.god_is_dead {
+            return false;
         }
-        self.turn == turn
+
+        self.turn == chat.next_player_turn()
+    }
+
+    /// gambling house rule, given cards already played this turn:
+    /// a point can be wagered on a second submission
+    pub fn can_wager(&self, chat: &chat::Model, played: i64) -> bool {
+        let pick = chat.pick as i64;
+        chat.gambling
+            && !chat.god_is_dead
+            && played >= pick
+            && played < pick * 2
+            // the point is wagered with the first card
+            && (played > pick || self.points > 0)
+    }
+
+    pub fn plain_name(&self) -> &str {
+        &self.name
     }
 
     pub fn tg_link(&self) -> String {
@@ -52,6 +73,11 @@ impl Model {
             self.telegram_id
         )
     }
+
+    /// adds the same user to another game
+    pub async fn copy<C: ConnectionTrait>(&self, conn: &C, chat_id: i32) -> Result<Model, DbErr> {
+        insert(conn, self.telegram_id, chat_id, self.name.clone()).await
+    }
 }
 
 pub async fn insert<C: ConnectionTrait>(
@@ -80,3 +106,18 @@ pub async fn insert<C: ConnectionTrait>(
     .insert(conn)
     .await
 }
+
+/// Telegram links of given users, using their most recent name
+pub async fn tg_links<C: ConnectionTrait>(
+    conn: &C,
+    telegram_ids: impl IntoIterator<Item = i64>,
+) -> Result<HashMap<i64, String>, DbErr> {
+    Ok(Entity::find()
+        .filter(Column::TelegramId.is_in(telegram_ids))
+        .order_by_asc(Column::Id)
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|player| (player.telegram_id, player.tg_link()))
+        .collect())
+}
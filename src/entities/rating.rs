@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use sea_orm::{entity::prelude::*, ActiveValue};
+
+/// Elo-style skill rating of a Telegram user, Rando Carlissian uses id 0
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "ratings")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub telegram_id: i64,
+    pub rating: i32,
+    pub games: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+pub const INITIAL_RATING: i32 = 1500;
+const K_FACTOR: f64 = 32.0;
+
+/// ratings of given Telegram users, unrated users get the initial rating
+pub async fn find<C: ConnectionTrait>(
+    conn: &C,
+    telegram_ids: impl IntoIterator<Item = i64>,
+) -> Result<HashMap<i64, i32>, DbErr> {
+    let telegram_ids = telegram_ids.into_iter().collect::<Vec<_>>();
+    let mut ratings = Entity::find()
+        .filter(Column::TelegramId.is_in(telegram_ids.iter().copied()))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|rating| (rating.telegram_id, rating.rating))
+        .collect::<HashMap<_, _>>();
+    for telegram_id in telegram_ids {
+        ratings.entry(telegram_id).or_insert(INITIAL_RATING);
+    }
+    Ok(ratings)
+}
+
+/// updates ratings given a game final standings,
+/// every participant is compared against every other one
+pub async fn update<C: ConnectionTrait>(conn: &C, standings: &[(i64, i64)]) -> Result<(), DbErr> {
+    if standings.len() < 2 {
+        return Ok(());
+    }
+
+    let existing = Entity::find()
+        .filter(Column::TelegramId.is_in(standings.iter().map(|(telegram_id, _)| *telegram_id)))
+        .all(conn)
+        .await?
+        .into_iter()
+        .map(|rating| (rating.telegram_id, rating))
+        .collect::<HashMap<_, _>>();
+    let rating = |telegram_id: &i64| {
+        existing
+            .get(telegram_id)
+            .map(|rating| rating.rating)
+            .unwrap_or(INITIAL_RATING) as f64
+    };
+
+    let opponents = (standings.len() - 1) as f64;
+    for (telegram_id, points) in standings {
+        let own = rating(telegram_id);
+        let delta = standings
+            .iter()
+            .filter(|(other_id, _)| other_id != telegram_id)
+            .map(|(other_id, other_points)| {
+                let score = match points.cmp(other_points) {
+                    std::cmp::Ordering::Greater => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Less => 0.0,
+                };
+                let expected = 1.0 / (1.0 + 10_f64.powf((rating(other_id) - own) / 400.0));
+                score - expected
+            })
+            .sum::<f64>()
+            * K_FACTOR
+            / opponents;
+
+        let new_rating = (own + delta).round() as i32;
+        if let Some(current) = existing.get(telegram_id) {
+            ActiveModel {
+                telegram_id: ActiveValue::Set(*telegram_id),
+                rating: ActiveValue::Set(new_rating),
+                games: ActiveValue::Set(current.games + 1),
+            }
+            .update(conn)
+            .await?;
+        } else {
+            ActiveModel {
+                telegram_id: ActiveValue::Set(*telegram_id),
+                rating: ActiveValue::Set(new_rating),
+                games: ActiveValue::Set(1),
+            }
+            .insert(conn)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
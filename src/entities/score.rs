@@ -0,0 +1,56 @@
+use chrono::{Datelike, Days, NaiveDate, Utc};
+use sea_orm::{entity::prelude::*, sea_query::OnConflict, ActiveValue};
+
+/// weekly wins of a Telegram user, kept up to date for the global leaderboard
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "scores")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub telegram_id: i64,
+    /// week's monday
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub week: NaiveDate,
+    pub rounds_won: i32,
+    pub games_won: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// current week's monday
+pub fn current_week() -> NaiveDate {
+    let today = Utc::now().date_naive();
+    today
+        .checked_sub_days(Days::new(today.weekday().num_days_from_monday().into()))
+        .unwrap_or(today)
+}
+
+/// adds wins to current week
+pub async fn add<C: ConnectionTrait>(
+    conn: &C,
+    telegram_id: i64,
+    rounds_won: i32,
+    games_won: i32,
+) -> Result<(), DbErr> {
+    Entity::insert(ActiveModel {
+        telegram_id: ActiveValue::Set(telegram_id),
+        week: ActiveValue::Set(current_week()),
+        rounds_won: ActiveValue::Set(rounds_won),
+        games_won: ActiveValue::Set(games_won),
+    })
+    .on_conflict(
+        OnConflict::columns([Column::TelegramId, Column::Week])
+            .value(
+                Column::RoundsWon,
+                Expr::col(Column::RoundsWon).add(rounds_won),
+            )
+            .value(Column::GamesWon, Expr::col(Column::GamesWon).add(games_won))
+            .to_owned(),
+    )
+    .exec(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, str::FromStr};
+
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime, Utc};
+use sea_orm::{entity::prelude::*, ActiveValue, QueryOrder, QuerySelect, StreamTrait};
+
+use super::{chat, season_standing};
+
+/// archived seasons
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "seasons")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub start_date: NaiveDate,
+    /// excluded
+    pub end_date: NaiveDate,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "season_standing::Entity")]
+    Standing,
+}
+
+impl Related<season_standing::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Standing.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Period {
+    Monthly,
+    Quarterly,
+}
+
+impl FromStr for Period {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "monthly" => Ok(Period::Monthly),
+            "quarterly" => Ok(Period::Quarterly),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Period {
+    fn months(&self) -> u32 {
+        match self {
+            Period::Monthly => 1,
+            Period::Quarterly => 3,
+        }
+    }
+
+    /// bounds of the season containing given date, end excluded
+    pub fn bounds(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let month = (date.month0() / self.months()) * self.months() + 1;
+        let start = NaiveDate::from_ymd_opt(date.year(), month, 1).unwrap_or(date);
+        let end = start
+            .checked_add_months(Months::new(self.months()))
+            .unwrap_or(start);
+        (start, end)
+    }
+
+    /// bounds of the current season, end excluded
+    pub fn current(&self) -> (NaiveDate, NaiveDate) {
+        self.bounds(Utc::now().date_naive())
+    }
+
+    pub fn name(&self, start: NaiveDate) -> String {
+        match self {
+            Period::Monthly => start.format("%B %Y").to_string(),
+            Period::Quarterly => format!("Q{} {}", start.month0() / 3 + 1, start.year()),
+        }
+    }
+}
+
+/// a player standing in a season
+pub struct Standing {
+    pub telegram_id: i64,
+    pub games_won: i64,
+    pub points: i64,
+}
+
+/// season standings of a Telegram chat, or global if `None`,
+/// computed from games closed between given dates, end excluded
+pub async fn standings<C>(
+    conn: &C,
+    start: NaiveDate,
+    end: NaiveDate,
+    telegram_id: Option<i64>,
+) -> Result<Vec<Standing>, DbErr>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let mut filter = chat::Column::EndDate
+        .gte(NaiveDateTime::from(start))
+        .and(chat::Column::EndDate.lt(NaiveDateTime::from(end)));
+    filter = match telegram_id {
+        Some(telegram_id) => filter.and(chat::Column::TelegramId.eq(telegram_id)),
+        None => filter.and(chat::Column::GlobalLeaderboard.eq(true)),
+    };
+    let chat_ids = chat::Entity::find()
+        .filter(filter)
+        .select_only()
+        .column(chat::Column::Id)
+        .into_tuple::<i32>()
+        .all(conn)
+        .await?;
+
+    let mut standings = HashMap::<_, Standing>::new();
+    for game in chat::standings(conn, &chat_ids).await?.values() {
+        for (telegram_id, points) in game {
+            standings
+                .entry(*telegram_id)
+                .or_insert_with(|| Standing {
+                    telegram_id: *telegram_id,
+                    games_won: 0,
+                    points: 0,
+                })
+                .points += points;
+        }
+        for telegram_id in chat::winners(game) {
+            if let Some(standing) = standings.get_mut(&telegram_id) {
+                standing.games_won += 1;
+            }
+        }
+    }
+
+    let mut standings = standings
+        .into_values()
+        // Rando Carlissian doesn't take part in global seasons
+        .filter(|standing| telegram_id.is_some() || standing.telegram_id > 0)
+        .collect::<Vec<_>>();
+    standings.sort_by(|a, b| {
+        b.games_won
+            .cmp(&a.games_won)
+            .then(b.points.cmp(&a.points))
+            .then(a.telegram_id.cmp(&b.telegram_id))
+    });
+    Ok(standings)
+}
+
+/// archives every season ended since the last archived one,
+/// returns them along with the Telegram chats that took part
+pub async fn archive<C>(conn: &C, period: Period) -> Result<Vec<(Model, Vec<i64>)>, DbErr>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let (current_start, _) = period.current();
+    let Some(last) = Entity::find()
+        .order_by_desc(Column::EndDate)
+        .one(conn)
+        .await?
+    else {
+        // first run, seasons are tracked from now on with an empty marker
+        ActiveModel {
+            start_date: ActiveValue::Set(current_start),
+            end_date: ActiveValue::Set(current_start),
+            ..Default::default()
+        }
+        .insert(conn)
+        .await?;
+        return Ok(Vec::new());
+    };
+
+    // the bot may have been down for more than a season
+    let mut archived = Vec::new();
+    let mut start = last.end_date;
+    while start < current_start {
+        let (_, end) = period.bounds(start);
+        archived.push(archive_season(conn, start, end.min(current_start)).await?);
+        start = end;
+    }
+
+    Ok(archived)
+}
+
+async fn archive_season<C>(
+    conn: &C,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<(Model, Vec<i64>), DbErr>
+where
+    C: ConnectionTrait + StreamTrait,
+{
+    let season = ActiveModel {
+        start_date: ActiveValue::Set(start),
+        end_date: ActiveValue::Set(end),
+        ..Default::default()
+    }
+    .insert(conn)
+    .await?;
+
+    let mut telegram_ids = chat::Entity::find()
+        .filter(
+            chat::Column::EndDate
+                .gte(NaiveDateTime::from(start))
+                .and(chat::Column::EndDate.lt(NaiveDateTime::from(end))),
+        )
+        .select_only()
+        .column(chat::Column::TelegramId)
+        .distinct()
+        .into_tuple::<i64>()
+        .all(conn)
+        .await?;
+    telegram_ids.sort_unstable();
+
+    // global standings use chat id 0
+    for chat_id in telegram_ids.iter().copied().map(Some).chain([None]) {
+        let standings = standings(conn, start, end, chat_id).await?;
+        for (position, standing) in standings.into_iter().enumerate() {
+            season_standing::ActiveModel {
+                season_id: ActiveValue::Set(season.id),
+                chat_id: ActiveValue::Set(chat_id.unwrap_or_default()),
+                telegram_id: ActiveValue::Set(standing.telegram_id),
+                position: ActiveValue::Set(position as i32 + 1),
+                games_won: ActiveValue::Set(standing.games_won as i32),
+                points: ActiveValue::Set(standing.points as i32),
+            }
+            .insert(conn)
+            .await?;
+        }
+    }
+
+    Ok((season, telegram_ids))
+}
@@ -0,0 +1,33 @@
+use sea_orm::entity::prelude::*;
+
+use super::season;
+
+/// archived final standings of a season
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "season_standings")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub season_id: i32,
+    /// Telegram chat id, 0 for the global leaderboard
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub chat_id: i64,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub telegram_id: i64,
+    pub position: i32,
+    pub games_won: i32,
+    pub points: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_one = "season::Entity")]
+    Season,
+}
+
+impl Related<season::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Season.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
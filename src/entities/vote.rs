@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use sea_orm::{entity::prelude::*, ActiveValue, QuerySelect};
+
+use super::chat;
+
+/// audience votes on the current turn's submissions
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[sea_orm(table_name = "votes")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub chat_id: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub turn: i32,
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub voter_id: i32,
+    /// voted submission's player id
+    pub player_id: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+/// votes for a submission of the current turn, a previous vote of the same player is replaced
+pub async fn cast<C: ConnectionTrait>(
+    conn: &C,
+    chat: &chat::Model,
+    voter_id: i32,
+    player_id: i32,
+) -> Result<(), DbErr> {
+    let vote = ActiveModel {
+        chat_id: ActiveValue::Set(chat.id),
+        turn: ActiveValue::Set(chat.turn),
+        voter_id: ActiveValue::Set(voter_id),
+        player_id: ActiveValue::Set(player_id),
+    };
+    if Entity::find_by_id((chat.id, chat.turn, voter_id))
+        .one(conn)
+        .await?
+        .is_some()
+    {
+        vote.update(conn).await?;
+    } else {
+        vote.insert(conn).await?;
+    }
+
+    Ok(())
+}
+
+/// votes received by every submission of the current turn
+pub async fn tally<C: ConnectionTrait>(
+    conn: &C,
+    chat: &chat::Model,
+) -> Result<HashMap<i32, i64>, DbErr> {
+    Ok(Entity::find()
+        .filter(Column::ChatId.eq(chat.id).and(Column::Turn.eq(chat.turn)))
+        .select_only()
+        .column(Column::PlayerId)
+        .column_as(Column::VoterId.count(), "votes")
+        .group_by(Column::PlayerId)
+        .into_tuple::<(i32, i64)>()
+        .all(conn)
+        .await?
+        .into_iter()
+        .collect())
+}
+
+/// most voted submissions' player ids, ties included
+pub async fn favourites<C: ConnectionTrait>(
+    conn: &C,
+    chat: &chat::Model,
+) -> Result<Vec<i32>, DbErr> {
+    let tally = tally(conn, chat).await?;
+    let Some(max) = tally.values().max().copied() else {
+        return Ok(Vec::new());
+    };
+
+    let mut favourites = tally
+        .into_iter()
+        .filter_map(|(player_id, votes)| (votes == max).then_some(player_id))
+        .collect::<Vec<_>>();
+    favourites.sort_unstable();
+    Ok(favourites)
+}